@@ -0,0 +1,72 @@
+//! Tool calls against a list of `TODOS` todos in each storage backend, to
+//! show what `TODO_STORAGE=sqlite` buys as lists grow. Run with
+//! `cargo bench --bench storage`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri_todo_mcp_lib::TodoRouter;
+
+const TODOS: u64 = 1000;
+
+/// A router over a fresh `store.json` in `dir` holding `TODOS` todos, using
+/// the `storage` backend. The SQLite database is filled from `store.json`
+/// on first open, as it would be for an existing user.
+fn router(dir: &std::path::Path, storage: &str) -> TodoRouter<MockRuntime> {
+    let todos: Vec<Value> = (1..=TODOS)
+        .map(|id| {
+            json!({
+                "id": id,
+                "text": format!("Todo number {}", id),
+                "done": id % 3 == 0,
+                "tags": ["bench"],
+                "created_at": "2025-01-01T00:00:00Z"
+            })
+        })
+        .collect();
+    std::fs::write(dir.join("store.json"), json!({ "todos": todos }).to_string()).unwrap();
+    // Read by `TodoRouter::new`. Benchmarks run one at a time, so nothing
+    // else reads the environment meanwhile.
+    std::env::set_var("TODO_STORAGE", storage);
+    let mut context = mock_context(noop_assets());
+    context.config_mut().identifier = dir.to_string_lossy().into_owned();
+    let app = mock_builder()
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .build(context)
+        .unwrap();
+    TodoRouter::new(app.handle().clone()).unwrap()
+}
+
+fn call(router: &TodoRouter<MockRuntime>, name: &str, arguments: Value) {
+    tauri::async_runtime::block_on(mcp_server::Router::call_tool(router, name, arguments))
+        .unwrap();
+}
+
+fn storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("storage");
+    for storage in ["json", "sqlite"] {
+        let dir = tempfile::tempdir().unwrap();
+        let router = router(dir.path(), storage);
+        group.bench_function(BenchmarkId::new("get_todos", storage), |b| {
+            b.iter(|| call(&router, "get_todos", json!({})))
+        });
+        group.bench_function(BenchmarkId::new("open_todos", storage), |b| {
+            b.iter(|| call(&router, "open_todos", json!({})))
+        });
+        let mut done = false;
+        group.bench_function(BenchmarkId::new("update_todo", storage), |b| {
+            b.iter(|| {
+                done = !done;
+                call(
+                    &router,
+                    "update_todo",
+                    json!({ "id": TODOS / 2, "text": "Toggled", "done": done }),
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, storage);
+criterion_main!(benches);
@@ -1,5 +1,9 @@
 mod mcp;
 
+/// Exposed for the benchmarks, which drive the tools the way an MCP client does.
+pub use mcp::TodoRouter;
+use tauri::Manager;
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -8,29 +12,50 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let is_mcp = std::env::args_os().any(|a| a == "mcp");
+    let builder = if is_mcp { mcp_builder() } else { app_builder() };
+    builder
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+/// Running as an MCP server needs nothing but the store: no window, no
+/// commands and no plugins the webview would use.
+fn mcp_builder() -> tauri::Builder<tauri::Wry> {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .setup(|app| {
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let code = match crate::mcp::start_server(handle.clone()).await {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        eprintln!("tauri-todo-mcp: {}", e);
+                        1
+                    }
+                };
+                handle.exit(code);
+            });
+            Ok(())
+        })
+}
+
+fn app_builder() -> tauri::Builder<tauri::Wry> {
     tauri::Builder::default()
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .setup(|app| {
-            let is_mcp = std::env::args_os().find(|a| a == "mcp").is_some();
-            if is_mcp {
-                let handle = app.handle().clone();
-                tauri::async_runtime::spawn(async move {
-                    crate::mcp::start_server(handle.clone()).await.unwrap();
-                    handle.exit(0);
-                });
-            } else {
-                tauri::webview::WebviewWindowBuilder::new(
-                    app,
-                    "main",
-                    tauri::WebviewUrl::App("index.html".into()),
-                )
-                .title("tauri-todo-mcp")
-                .build()?;
-            }
+            let router = crate::mcp::TodoRouter::new(app.handle().clone())?;
+            tauri::async_runtime::spawn(crate::mcp::run_reminder_checks(router.clone()));
+            app.manage(router);
+            tauri::webview::WebviewWindowBuilder::new(
+                app,
+                "main",
+                tauri::WebviewUrl::App("index.html".into()),
+            )
+            .title("tauri-todo-mcp")
+            .build()?;
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![greet])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(tauri::generate_handler![greet, mcp::invoke_mcp_tool])
 }
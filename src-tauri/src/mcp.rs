@@ -1,139 +1,7833 @@
-use std::{future::Future, pin::Pin};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
+use chrono::{
+    DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveTime, Offset, TimeZone, Utc, Weekday,
+};
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
 use mcp_core::{
+    content::Annotations,
     handler::{PromptError, ResourceError},
     prompt::Prompt,
     protocol::ServerCapabilities,
+    resource::Resource,
     Content, Tool, ToolError,
 };
 use mcp_server::{
     router::{CapabilitiesBuilder, RouterService},
     ByteTransport, Server,
 };
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Wry};
 use tauri_plugin_store::StoreExt;
-use tokio::io::{stdin, stdout};
+use tokio::io::{stdin, stdout, AsyncRead, AsyncWrite};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use storage::{JsonStorage, SqliteStorage, TodoStorage};
+
+mod storage;
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct Todo {
     id: u64,
     text: String,
     done: bool,
+    /// Derived from `done` for todos stored before it existed.
+    #[serde(default)]
+    status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    /// Whether `priority` was last set by `auto_prioritize` rather than by
+    /// the user.
+    #[serde(default, skip_serializing_if = "is_false")]
+    priority_auto: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    subtasks: Vec<Subtask>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    spent_minutes: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    reminders: Vec<String>,
+    /// Ids of todos in the same list that must be done first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<u64>,
+    /// Reminders at or before this time have been dismissed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reminders_dismissed_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reminder_schedule: Option<ReminderSchedule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<Revision>,
+    /// Short per-list reference, written `#42`, that users can say instead
+    /// of the id. Assigned in list order to todos that don't have one yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    number: Option<u64>,
+    /// Fields this version doesn't know about, written by newer clients or by
+    /// hand. They are kept as-is so saving a todo never drops them.
+    #[serde(flatten)]
+    extra: serde_json::Map<String, Value>,
 }
 
-#[derive(Clone)]
-pub struct TodoRouter {
-    app: AppHandle,
+/// The user-editable state of a todo before an edit, kept so that a single
+/// todo can be reverted with `revert_todo`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Revision {
+    text: String,
+    done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    recorded_at: String,
 }
 
-const STORE_PATH: &str = "store.json";
-const TODOS_KEY: &str = "todos";
+impl Todo {
+    /// Stamps `updated_at`, and sets or clears `completed_at` to follow the
+    /// done state.
+    fn touch(&mut self) {
+        let now = Utc::now().to_rfc3339();
+        if !self.done {
+            self.completed_at = None;
+        } else if self.completed_at.is_none() {
+            self.completed_at = Some(now.clone());
+        }
+        self.updated_at = Some(now);
+        self.sync_status();
+    }
 
-impl TodoRouter {
-    pub fn new(app: AppHandle) -> Self {
-        Self { app }
+    /// Makes `status` agree with `done`, which wins when they differ. Open
+    /// todos keep their status unless it says `done`.
+    fn sync_status(&mut self) {
+        if self.done {
+            self.status = Status::Done;
+        } else if self.status == Status::Done {
+            self.status = Status::Todo;
+        }
     }
 
-    fn get_todos(&self) -> Result<Vec<Todo>, ToolError> {
-        let store = self
-            .app
-            .store(STORE_PATH)
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        store
-            .reload()
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        let todos = store
-            .get(TODOS_KEY)
-            .and_then(|value| serde_json::from_value::<Vec<Todo>>(value).ok())
-            .unwrap_or_else(|| vec![]);
-        Ok(todos)
+    fn revision(&self) -> Revision {
+        Revision {
+            text: self.text.clone(),
+            done: self.done,
+            priority: self.priority,
+            recorded_at: Utc::now().to_rfc3339(),
+        }
     }
 
-    fn add_todo(&self, text: String) -> Result<Todo, ToolError> {
-        let store = self
-            .app
-            .store(STORE_PATH)
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        let mut todos = self.get_todos()?;
-        let id = chrono::Utc::now().timestamp_millis() as u64;
-        let todo = Todo {
-            id,
-            text,
-            done: false,
-        };
-        todos.push(todo.clone());
-        store.set(
-            TODOS_KEY,
-            serde_json::to_value(todos).map_err(|e| ToolError::ExecutionError(e.to_string()))?,
-        );
-        store
-            .save()
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        Ok(todo)
+    /// Pushes `before` onto the history if the edit changed the text, done
+    /// state or priority, keeping at most `MAX_REVISIONS` entries.
+    fn record_revision(&mut self, before: Revision) {
+        if before.text == self.text && before.done == self.done && before.priority == self.priority
+        {
+            return;
+        }
+        self.history.push(before);
+        if self.history.len() > MAX_REVISIONS {
+            self.history.remove(0);
+        }
     }
 
-    fn remove_todo(&self, id: u64) -> Result<(), ToolError> {
-        let store = self
-            .app
-            .store(STORE_PATH)
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        let mut todos = self.get_todos()?;
-        todos.retain(|todo| todo.id != id);
-        store.set(
-            TODOS_KEY,
-            serde_json::to_value(todos).map_err(|e| ToolError::ExecutionError(e.to_string()))?,
-        );
-        store
-            .save()
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        Ok(())
+    /// Whether a reminder, one-off or scheduled, has fired since the last
+    /// dismissal.
+    fn reminder_due(&self, now: DateTime<Utc>) -> bool {
+        self.latest_reminder(now).is_some()
     }
 
-    fn update_todo(&self, todo: Todo) -> Result<(), ToolError> {
-        let store = self
-            .app
-            .store(STORE_PATH)
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        let mut todos = self.get_todos()?;
-        if let Some(index) = todos.iter().position(|t| t.id == todo.id) {
-            todos[index] = todo;
-            store.set(
-                TODOS_KEY,
-                serde_json::to_value(todos)
-                    .map_err(|e| ToolError::ExecutionError(e.to_string()))?,
-            );
+    /// When the most recent reminder that has fired since the last dismissal
+    /// went off. A repeating schedule moves this forward with each occurrence.
+    fn latest_reminder(&self, now: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+        let dismissed = self.reminders_dismissed_at.as_deref().and_then(parse_date);
+        self.reminders
+            .iter()
+            .filter_map(|reminder| parse_date(reminder))
+            .chain(
+                self.reminder_schedule
+                    .as_ref()
+                    .and_then(|schedule| schedule.latest(now)),
+            )
+            .filter(|at| *at <= now && dismissed.is_none_or(|dismissed| *at > dismissed))
+            .max()
+    }
+
+    /// Todos written before timestamps were tracked have neither field and
+    /// are never reported as changed.
+    fn changed_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.updated_at
+            .as_deref()
+            .or(self.created_at.as_deref())
+            .and_then(parse_date)
+    }
+}
+
+/// A reminder that repeats `every` interval (in the `parse_due_offset`
+/// format, such as `1 day` or `12h`) from `starts_at`, independently of the
+/// due date. Each occurrence fires like a one-off reminder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReminderSchedule {
+    every: String,
+    starts_at: String,
+}
+
+impl ReminderSchedule {
+    /// The last occurrence at or before `now`, if the schedule has started.
+    fn latest(&self, now: DateTime<Utc>) -> Option<DateTime<FixedOffset>> {
+        let start = parse_date(&self.starts_at)?;
+        let every = parse_due_offset(&self.every)?.num_seconds();
+        let elapsed = (now - start.with_timezone(&Utc)).num_seconds();
+        if elapsed < 0 || every <= 0 {
+            return None;
         }
-        store
-            .save()
-            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
-        Ok(())
+        start.checked_add_signed(chrono::Duration::seconds(elapsed / every * every))
     }
 }
 
-impl mcp_server::Router for TodoRouter {
-    fn name(&self) -> String {
-        "todo".to_string()
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Tombstone {
+    id: u64,
+    deleted_at: String,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Subtask {
+    text: String,
+    done: bool,
+}
+
+/// Where a todo stands. `done` is kept in step: a todo is `Done` exactly
+/// when `done` is true.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    #[default]
+    Todo,
+    InProgress,
+    Blocked,
+    Done,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+        }
     }
+}
 
-    fn instructions(&self) -> String {
-        "This server allows you to manage todos with persistent storage. You can retrieve the current list of todos using `get_todos`, add a new todo with `add_todo`, remove a specific todo by its ID using `remove_todo`, and update an existing todo with `update_todo`.".to_string()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
     }
+}
 
-    fn capabilities(&self) -> ServerCapabilities {
-        CapabilitiesBuilder::new()
-            .with_tools(false)
-            .with_resources(false, false)
-            .with_prompts(false)
-            .build()
+/// The layout arguments of the tools that return many todos; see
+/// `todos_response`.
+#[derive(Debug, Deserialize)]
+struct PageArgs {
+    offset: Option<usize>,
+    #[serde(default, deserialize_with = "positive")]
+    limit: Option<usize>,
+    #[serde(default, deserialize_with = "positive")]
+    chunk_size: Option<usize>,
+    #[serde(default)]
+    include_summary: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TodoPage {
+    todos: Vec<Todo>,
+    total: usize,
+    next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Snapshot {
+    name: String,
+    created_at: String,
+    todos: Vec<Todo>,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotInfo {
+    name: String,
+    created_at: String,
+    todos: usize,
+}
+
+impl From<&Snapshot> for SnapshotInfo {
+    fn from(snapshot: &Snapshot) -> Self {
+        Self {
+            name: snapshot.name.clone(),
+            created_at: snapshot.created_at.clone(),
+            todos: snapshot.todos.len(),
+        }
     }
+}
 
-    fn list_tools(&self) -> Vec<Tool> {
-        vec![
+#[derive(Debug, Serialize)]
+struct Changes {
+    todos: Vec<Todo>,
+    deleted: Vec<u64>,
+    server_time: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Workload {
+    open_todos: usize,
+    unestimated_todos: usize,
+    estimated_minutes: u64,
+    spent_minutes: u64,
+    remaining_minutes: u64,
+}
+
+impl Workload {
+    /// Adds the open `todo` to the totals.
+    fn add(&mut self, todo: &Todo) {
+        self.open_todos += 1;
+        self.spent_minutes += todo.spent_minutes as u64;
+        match todo.estimate_minutes {
+            Some(estimate) => {
+                self.estimated_minutes += estimate as u64;
+                self.remaining_minutes += estimate.saturating_sub(todo.spent_minutes) as u64;
+            }
+            None => self.unestimated_todos += 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AssigneeWorkload {
+    /// `None` for the todos nobody is assigned to.
+    assignee: Option<String>,
+    #[serde(flatten)]
+    workload: Workload,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CallStats {
+    count: u64,
+    total: Duration,
+    last: Duration,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolMetrics {
+    count: u64,
+    average_ms: f64,
+    last_ms: f64,
+}
+
+impl From<&CallStats> for ToolMetrics {
+    fn from(stats: &CallStats) -> Self {
+        Self {
+            count: stats.count,
+            average_ms: stats.total.as_secs_f64() * 1000.0 / stats.count.max(1) as f64,
+            last_ms: stats.last.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Streak {
+    current: u32,
+    longest: u32,
+}
+
+/// Predicates of `query_todos`, read from the `filter` object of the tools
+/// that select todos. Every field that is set must match.
+#[derive(Debug, Default, Deserialize)]
+struct TodoQuery {
+    done: Option<bool>,
+    #[serde(default, deserialize_with = "tag_list")]
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    status: Option<Status>,
+    #[serde(default, deserialize_with = "optional_trimmed")]
+    text_contains: Option<String>,
+    #[serde(default, deserialize_with = "optional_date")]
+    due_before: Option<DateTime<FixedOffset>>,
+}
+
+/// The `filter` of the tools that change the todos they match. It is
+/// required, so a forgotten filter can't select the whole list.
+#[derive(Debug, Deserialize)]
+struct FilterArgs {
+    filter: TodoQuery,
+}
+
+/// The `filter` of the tools that only read the todos they match; without
+/// one they match every todo.
+#[derive(Debug, Deserialize)]
+struct QueryArgs {
+    filter: Option<TodoQuery>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// How `sort_todos`, and the `sort` object of `query_todos`, order todos.
+#[derive(Debug, Deserialize)]
+struct SortArgs {
+    #[serde(deserialize_with = "trimmed")]
+    by: String,
+    #[serde(default)]
+    order: Order,
+    locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuerySortArgs {
+    sort: Option<SortArgs>,
+}
+
+impl TodoQuery {
+    /// Tags must all be present, text matching ignores case, and todos
+    /// without a due date never match `due_before`.
+    fn matches(&self, todo: &Todo) -> bool {
+        self.done.is_none_or(|done| todo.done == done)
+            && self.tags.iter().all(|tag| todo.tags.contains(tag))
+            && self
+                .priority
+                .is_none_or(|priority| todo.priority == Some(priority))
+            && self.status.is_none_or(|status| todo.status == status)
+            && self
+                .text_contains
+                .as_ref()
+                .is_none_or(|needle| todo.text.to_lowercase().contains(&needle.to_lowercase()))
+            && self.due_before.is_none_or(|before| {
+                todo.due_date
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|due| due < before)
+            })
+    }
+}
+
+/// Text cleanups applied by `normalize_todos`; each is opt-in.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+struct Normalization {
+    trim: bool,
+    collapse_spaces: bool,
+    title_case: bool,
+    strip_trailing_punctuation: bool,
+}
+
+impl Normalization {
+    /// Returns the normalized text. Title casing only upper-cases the first
+    /// letter of each word, so acronyms survive.
+    fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.collapse_spaces {
+            let mut collapsed = String::with_capacity(text.len());
+            for c in text.chars() {
+                if c == ' ' && collapsed.ends_with(' ') {
+                    continue;
+                }
+                collapsed.push(c);
+            }
+            text = collapsed;
+        }
+        if self.trim {
+            text = text.trim().to_string();
+        }
+        if self.strip_trailing_punctuation {
+            let stripped = text.trim_end_matches(|c: char| ".,;:!?。、！？…".contains(c));
+            if !stripped.trim().is_empty() {
+                text = stripped.to_string();
+            }
+        }
+        if self.title_case {
+            let mut titled = String::with_capacity(text.len());
+            let mut word_start = true;
+            for c in text.chars() {
+                if word_start {
+                    titled.extend(c.to_uppercase());
+                } else {
+                    titled.push(c);
+                }
+                word_start = c.is_whitespace();
+            }
+            text = titled;
+        }
+        text
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StoreStats {
+    path: String,
+    size_bytes: u64,
+    todos: usize,
+    average_bytes_per_todo: u64,
+    warn_bytes: u64,
+    warning: bool,
+}
+
+/// Tag to todo count, serialized as a JSON object in descending count order.
+#[derive(Debug)]
+struct TagCounts(Vec<(String, usize)>);
+
+impl Serialize for TagCounts {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(tag, count)| (tag, count)))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Activity {
+    /// `created` when the todo hasn't been changed since it was added,
+    /// otherwise `modified`.
+    kind: &'static str,
+    at: String,
+    todo: Todo,
+}
+
+/// Thresholds used by `auto_prioritize`, in days. Overdue todos and those due
+/// within `high_within_days` become high; those due within
+/// `medium_within_days`, or without a due date but older than
+/// `old_after_days`, become medium; everything else becomes low.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+struct PriorityRules {
+    high_within_days: u32,
+    medium_within_days: u32,
+    old_after_days: u32,
+}
+
+impl Default for PriorityRules {
+    fn default() -> Self {
+        Self {
+            high_within_days: 2,
+            medium_within_days: 7,
+            old_after_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AutoPrioritizeArgs {
+    #[serde(default)]
+    respect_manual: bool,
+}
+
+impl PriorityRules {
+    fn priority(&self, todo: &Todo, now: DateTime<Utc>) -> Priority {
+        // A window reaching past the last representable date covers every
+        // due date, and a cutoff before the first one covers none.
+        let within = |due: DateTime<FixedOffset>, days: u32| {
+            now.checked_add_signed(chrono::Duration::days(days.into()))
+                .is_none_or(|limit| due <= limit)
+        };
+        match todo.due_date.as_deref().and_then(parse_date) {
+            Some(due) if within(due, self.high_within_days) => Priority::High,
+            Some(due) if within(due, self.medium_within_days) => Priority::Medium,
+            Some(_) => Priority::Low,
+            None => {
+                let old = todo
+                    .created_at
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|created| {
+                        now.checked_sub_signed(chrono::Duration::days(self.old_after_days.into()))
+                            .is_some_and(|cutoff| created < cutoff)
+                    });
+                if old {
+                    Priority::Medium
+                } else {
+                    Priority::Low
+                }
+            }
+        }
+    }
+}
+
+/// Per-list options, stored in `store.json` whichever backend holds the
+/// todos.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ListSettings {
+    /// Offset such as `+3 days` used as the due date of new todos that don't
+    /// get one explicitly. An explicit `due_date` always wins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_due_offset: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StoreInfo {
+    /// Absolute path of `store.json`, resolved against the app data
+    /// directory just like the store plugin does.
+    path: String,
+    exists: bool,
+    size_bytes: u64,
+    storage: &'static str,
+    /// Where the todo lists themselves are kept; differs from `path` with the
+    /// SQLite backend.
+    storage_path: String,
+}
+
+/// Subtask completion of a single todo. All zeros when it has no subtasks.
+#[derive(Debug, Serialize)]
+struct Progress {
+    done: usize,
+    total: usize,
+    /// Rounded down, so 100 only once every subtask is done.
+    percent: u32,
+}
+
+/// Desired state pushed with `upsert_todo`.
+#[derive(Debug, Deserialize)]
+struct Upsert {
+    id: Option<u64>,
+    #[serde(deserialize_with = "non_empty")]
+    text: String,
+    done: Option<bool>,
+    #[serde(default, deserialize_with = "optional_date")]
+    due_date: Option<DateTime<FixedOffset>>,
+    priority: Option<Priority>,
+    #[serde(default, deserialize_with = "optional_tag_list")]
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct DueDateCheck {
+    /// The due date normalized to RFC3339, as `set_due_date` would store it.
+    due_date: String,
+    /// `past`, `today` or `future`. Anything on the current calendar day is
+    /// `today`, even if the time has already passed.
+    status: &'static str,
+    relative: String,
+}
+
+/// Arguments of the tools that only address a todo. Per-tool argument
+/// structs are parsed with `parse_args`; `list` is read separately by
+/// `list_name` since every tool takes it.
+#[derive(Debug, Deserialize)]
+struct IdArgs {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateArgs {
+    id: u64,
+    #[serde(deserialize_with = "non_empty")]
+    text: String,
+    done: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PriorityArgs {
+    id: u64,
+    priority: Option<Priority>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextArgs {
+    id: u64,
+    #[serde(deserialize_with = "non_empty")]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DoneArgs {
+    id: u64,
+    done: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubtaskArgs {
+    id: u64,
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubtaskDoneArgs {
+    id: u64,
+    index: usize,
+    done: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotesArgs {
+    id: u64,
+    notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddArgs {
+    #[serde(deserialize_with = "non_empty")]
+    text: String,
+    #[serde(default)]
+    done: bool,
+    #[serde(default, deserialize_with = "optional_date")]
+    due_date: Option<DateTime<FixedOffset>>,
+    priority: Option<Priority>,
+    #[serde(default, deserialize_with = "tag_list")]
+    tags: Vec<String>,
+    position: Option<u64>,
+    #[serde(default)]
+    return_list: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DueDateArgs {
+    id: u64,
+    #[serde(default, deserialize_with = "optional_date")]
+    due_date: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EstimateArgs {
+    id: u64,
+    minutes: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogTimeArgs {
+    id: u64,
+    minutes: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsArgs {
+    id: u64,
+    #[serde(deserialize_with = "tag_list")]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SplitArgs {
+    id: u64,
+    #[serde(deserialize_with = "text_list")]
+    texts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AttachmentArgs {
+    id: u64,
+    #[serde(deserialize_with = "trimmed")]
+    attachment: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReminderArgs {
+    id: u64,
+    #[serde(deserialize_with = "date")]
+    at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DependenciesArgs {
+    id: u64,
+    depends_on: Vec<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelatedArgs {
+    id: u64,
+    count: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IcsArgs {
+    #[serde(default)]
+    include_all: bool,
+}
+
+/// The `dry_run` flag of the tools that change every matching todo.
+#[derive(Debug, Deserialize)]
+struct DryRunArgs {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReopenArgs {
+    within_hours: u32,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Open todos of the current week by weekday, serialized as a JSON object
+/// with an `overdue` bucket first and the days in calendar order.
+#[derive(Debug)]
+struct WeekView(Vec<(&'static str, Vec<Todo>)>);
+
+impl Serialize for WeekView {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(day, todos)| (day, todos)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AssigneeArgs {
+    id: u64,
+    #[serde(default, deserialize_with = "optional_trimmed")]
+    assignee: Option<String>,
+}
+
+/// A list put away with `archive_list`. Archives always live in
+/// `store.json`, whatever the storage backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchivedList {
+    archived_at: String,
+    todos: Vec<Todo>,
+    /// The list's other `store.json` entries, keyed by prefix (see
+    /// `LIST_DATA_KEY_PREFIXES`), put back on restore.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    data: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveArgs {
+    ids: Vec<u64>,
+    #[serde(deserialize_with = "non_empty")]
+    from: String,
+    #[serde(deserialize_with = "non_empty")]
+    to: String,
+}
+
+/// Result of `move_todos`.
+#[derive(Debug, Serialize)]
+struct MoveResult {
+    moved: Vec<u64>,
+    not_found: Vec<u64>,
+}
+
+/// A day on which more open todos are due than `overloaded_days` allows.
+#[derive(Debug, Serialize)]
+struct OverloadedDay {
+    date: NaiveDate,
+    count: usize,
+    todos: Vec<Todo>,
+}
+
+/// Result of `schema_status`.
+#[derive(Debug, Serialize)]
+struct SchemaStatus {
+    schema_version: u64,
+    latest_version: u64,
+    pending: bool,
+    /// The version upgraded from when this server started, or null.
+    migrated_from: Option<u64>,
+}
+
+/// A self-contained, read-only copy of a list for sharing, produced by
+/// `export_snapshot`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotBundle {
+    format: String,
+    version: u64,
+    server: String,
+    server_version: String,
+    generated_at: String,
+    list: String,
+    todos: Vec<Todo>,
+}
+
+/// Result of `forecast`. `estimated_date` is null when nothing was completed
+/// in the window, with `explanation` saying so.
+#[derive(Debug, Serialize)]
+struct Forecast {
+    open_todos: usize,
+    window_days: u32,
+    completed_in_window: usize,
+    per_day: f64,
+    estimated_days: Option<u64>,
+    estimated_date: Option<NaiveDate>,
+    explanation: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetListArgs {
+    id: u64,
+    #[serde(deserialize_with = "non_empty")]
+    to: String,
+    #[serde(default)]
+    create: bool,
+}
+
+/// A todo present in both sides of `diff_snapshots` whose fields differ.
+/// Each changed field maps to `{before, after}`, null where it is unset.
+#[derive(Debug, Serialize)]
+struct TodoChange {
+    id: u64,
+    fields: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct SnapshotDiff {
+    from: String,
+    to: String,
+    added: Vec<Todo>,
+    removed: Vec<Todo>,
+    changed: Vec<TodoChange>,
+}
+
+#[derive(Debug, Serialize)]
+struct TagPair {
+    tags: [String; 2],
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct WipStatus {
+    tag: String,
+    in_progress: usize,
+    limit: usize,
+    over_limit: bool,
+    todos: Vec<Todo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusArgs {
+    id: u64,
+    status: Status,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScheduleArgs {
+    id: u64,
+    every: Option<String>,
+    starts_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListArgs {
+    list: Option<String>,
+}
+
+/// The list `move_todos` resolves `#42` references in.
+#[derive(Debug, Deserialize)]
+struct MoveFromArgs {
+    #[serde(deserialize_with = "non_empty")]
+    from: String,
+}
+
+/// The `timezone` of the tools that work with calendar days, a UTC offset
+/// such as `+09:00` (or `Z`/`UTC`) that defaults to the server's.
+#[derive(Debug, Deserialize)]
+struct TimezoneArgs {
+    #[serde(default, deserialize_with = "utc_offset")]
+    timezone: Option<FixedOffset>,
+}
+
+impl TimezoneArgs {
+    fn offset(&self) -> FixedOffset {
+        self.timezone.unwrap_or_else(|| *Local::now().offset())
+    }
+}
+
+/// The `return_list` flag, which asks a mutating tool to include the updated
+/// list in its result.
+#[derive(Debug, Deserialize)]
+struct ReturnListArgs {
+    #[serde(default)]
+    return_list: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NameArgs {
+    #[serde(deserialize_with = "trimmed")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagArgs {
+    #[serde(deserialize_with = "trimmed")]
+    tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RetagArgs {
+    #[serde(deserialize_with = "trimmed")]
+    from_tag: String,
+    #[serde(deserialize_with = "trimmed")]
+    to_tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RescheduleArgs {
+    #[serde(default, deserialize_with = "optional_date")]
+    to: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgendaArgs {
+    #[serde(default, deserialize_with = "optional_day")]
+    date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SinceArgs {
+    #[serde(deserialize_with = "date")]
+    since: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StaleArgs {
+    older_than_days: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MaxTextLenArgs {
+    #[serde(default, deserialize_with = "positive")]
+    max_text_len: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CountArgs {
+    count: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeekdayArgs {
+    #[serde(deserialize_with = "weekday")]
+    weekday: Weekday,
+}
+
+/// Which kinds of missing information `needs_triage` looks for; both by
+/// default.
+#[derive(Debug, Deserialize)]
+struct TriageArgs {
+    #[serde(default = "enabled")]
+    missing_due_date: bool,
+    #[serde(default = "enabled")]
+    missing_priority: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListSettingsArgs {
+    /// Absent keeps the current offset, null clears it.
+    #[serde(default, deserialize_with = "due_offset")]
+    default_due_offset: Option<Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TableArgs {
+    #[serde(default = "all_table_columns", deserialize_with = "table_columns")]
+    columns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistogramArgs {
+    #[serde(default, deserialize_with = "optional_day")]
+    from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "optional_day")]
+    to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LimitArgs {
+    #[serde(default, deserialize_with = "positive")]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckDueDateArgs {
+    #[serde(deserialize_with = "date")]
+    due_date: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RandomArgs {
+    #[serde(default, deserialize_with = "tag_list")]
+    tags: Vec<String>,
+    priority: Option<Priority>,
+    seed: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeekViewArgs {
+    #[serde(default)]
+    week_start: WeekStart,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrioritizeArgs {
+    priority: Priority,
+}
+
+#[derive(Debug, Deserialize)]
+struct OverloadedArgs {
+    max_per_day: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportArgs {
+    bundle: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrefixArgs {
+    #[serde(deserialize_with = "trimmed")]
+    prefix: String,
+    #[serde(default)]
+    unique: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastArgs {
+    #[serde(default, deserialize_with = "positive")]
+    window_days: Option<u32>,
+}
+
+/// The tags `tag_matching` adds, given as either `tag` or `tags`.
+#[derive(Debug, Deserialize)]
+struct TagMatchingArgs {
+    #[serde(default, deserialize_with = "optional_trimmed")]
+    tag: Option<String>,
+    #[serde(default, deserialize_with = "optional_tag_list")]
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DiffArgs {
+    #[serde(deserialize_with = "trimmed")]
+    from: String,
+    #[serde(default, deserialize_with = "optional_trimmed")]
+    to: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WipArgs {
+    limit: usize,
+    #[serde(default, deserialize_with = "optional_trimmed")]
+    tag: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutlineArgs {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetListsArgs {
+    #[serde(default)]
+    include_archived: bool,
+}
+
+pub struct TodoRouter<R: Runtime = Wry> {
+    app: AppHandle<R>,
+    default_list: String,
+    auto_complete_parent: bool,
+    auto_compact: bool,
+    check_attachments: bool,
+    max_lists: usize,
+    /// Most items a single tool call may pass in one array, import or
+    /// outline (`TODO_MAX_BATCH`).
+    max_batch: usize,
+    max_subtasks: usize,
+    /// Most open todos per list that `set_status` lets be `in_progress` at
+    /// once (`TODO_WIP_LIMIT`); `None` sets no limit.
+    wip_limit: Option<usize>,
+    /// How often `start_server` backs up `store.json`; `None` disables
+    /// backups.
+    backup_interval: Option<Duration>,
+    backup_keep: usize,
+    store_warn_bytes: u64,
+    /// `None` exposes every tool.
+    allowed_tools: Option<Arc<HashSet<String>>>,
+    storage: Arc<dyn TodoStorage>,
+    pretty_json: bool,
+    /// Whether results carry ids as strings (`TODO_ID_FORMAT=string`).
+    string_ids: bool,
+    instructions: Option<String>,
+    webhook_url: Option<String>,
+    http: reqwest::Client,
+    metrics: Arc<Mutex<HashMap<String, CallStats>>>,
+    /// Held with the lock on `LOCK_FILE` for the whole read-modify-write of a
+    /// mutating tool call so overlapping calls can't lose each other's
+    /// updates; see `lock_store`. Shared by every router in the process.
+    write_lock: Arc<Mutex<()>>,
+    /// The version `migrate_store` upgraded the store from, if it did.
+    migrated_from: Arc<std::sync::OnceLock<u64>>,
+}
+
+// Written out because `derive` would require `R: Clone`, which no runtime is.
+impl<R: Runtime> Clone for TodoRouter<R> {
+    fn clone(&self) -> Self {
+        Self {
+            app: self.app.clone(),
+            default_list: self.default_list.clone(),
+            auto_complete_parent: self.auto_complete_parent,
+            auto_compact: self.auto_compact,
+            check_attachments: self.check_attachments,
+            max_lists: self.max_lists,
+            max_batch: self.max_batch,
+            max_subtasks: self.max_subtasks,
+            wip_limit: self.wip_limit,
+            backup_interval: self.backup_interval,
+            backup_keep: self.backup_keep,
+            store_warn_bytes: self.store_warn_bytes,
+            allowed_tools: self.allowed_tools.clone(),
+            storage: self.storage.clone(),
+            pretty_json: self.pretty_json,
+            string_ids: self.string_ids,
+            instructions: self.instructions.clone(),
+            webhook_url: self.webhook_url.clone(),
+            http: self.http.clone(),
+            metrics: self.metrics.clone(),
+            write_lock: self.write_lock.clone(),
+            migrated_from: self.migrated_from.clone(),
+        }
+    }
+}
+
+const STORE_PATH: &str = "store.json";
+const TODOS_KEY: &str = "todos";
+const LIST_KEY_PREFIX: &str = "todos:";
+const TOMBSTONES_KEY_PREFIX: &str = "deleted:";
+const MAX_TOMBSTONES: usize = 1000;
+/// The largest id `upsert_todo` accepts: the largest integer a JavaScript
+/// client, the webview included, holds exactly.
+const MAX_TODO_ID: u64 = (1 << 53) - 1;
+const SNAPSHOTS_KEY_PREFIX: &str = "snapshots:";
+const SETTINGS_KEY_PREFIX: &str = "settings:";
+const FOCUS_KEY_PREFIX: &str = "focus:";
+const ARCHIVE_KEY_PREFIX: &str = "archived:";
+/// The per-list `store.json` entries besides the todos, which move into the
+/// archive with the list.
+const LIST_DATA_KEY_PREFIXES: &[&str] = &[
+    SETTINGS_KEY_PREFIX,
+    FOCUS_KEY_PREFIX,
+    TOMBSTONES_KEY_PREFIX,
+    SNAPSHOTS_KEY_PREFIX,
+];
+const MAX_REVISIONS: usize = 10;
+const TABLE_COLUMNS: &[&str] = &["id", "text", "status", "priority", "due_date"];
+const DEFAULT_RECENT_ACTIVITY: usize = 10;
+const MAX_RECENT_ACTIVITY: usize = 100;
+const DEFAULT_RELATED_TODOS: usize = 10;
+const MAX_RELATED_TODOS: usize = 100;
+const DEFAULT_LIST: &str = "default";
+const LIST_URI: &str = "todo://list";
+const DEFAULT_LIST_ENV: &str = "TODO_DEFAULT_LIST";
+const AUTO_COMPLETE_PARENT_ENV: &str = "TODO_AUTO_COMPLETE_PARENT";
+const AUTO_COMPACT_ENV: &str = "TODO_AUTO_COMPACT";
+const CHECK_ATTACHMENTS_ENV: &str = "TODO_CHECK_ATTACHMENTS";
+const MAX_LISTS_ENV: &str = "TODO_MAX_LISTS";
+const STORAGE_ENV: &str = "TODO_STORAGE";
+const SQLITE_PATH_ENV: &str = "TODO_SQLITE_PATH";
+const SQLITE_FILE: &str = "todos.sqlite3";
+const LOCK_FILE: &str = "store.json.lock";
+const STORE_WARN_BYTES_ENV: &str = "TODO_STORE_WARN_BYTES";
+const ALLOWED_TOOLS_ENV: &str = "TODO_ALLOWED_TOOLS";
+const DEFAULT_STORE_WARN_BYTES: u64 = 5_000_000;
+const DEFAULT_MAX_LISTS: usize = 100;
+const MAX_SUBTASKS_ENV: &str = "TODO_MAX_SUBTASKS";
+const DEFAULT_MAX_SUBTASKS: usize = 50;
+const MAX_BATCH_ENV: &str = "TODO_MAX_BATCH";
+const DEFAULT_MAX_BATCH: usize = 1000;
+const BACKUP_INTERVAL_ENV: &str = "TODO_BACKUP_INTERVAL_MINUTES";
+const BACKUP_KEEP_ENV: &str = "TODO_BACKUP_KEEP";
+const DEFAULT_BACKUP_KEEP: usize = 10;
+/// Directory next to `store.json` that holds the scheduled backups.
+const BACKUP_DIR: &str = "backups";
+const PRETTY_JSON_ENV: &str = "TODO_PRETTY_JSON";
+const ID_FORMAT_ENV: &str = "TODO_ID_FORMAT";
+/// Key in `store.json` recording the version of the stored data's layout.
+const SCHEMA_VERSION_KEY: &str = "schema_version";
+/// The layout this build writes. Data without `schema_version` predates
+/// versioning and counts as version 0; it reads the same as version 1.
+/// Version 2 stores a `status` on every todo.
+const SCHEMA_VERSION: u64 = 2;
+const DEFAULT_FORECAST_WINDOW_DAYS: u32 = 14;
+/// Tag `wip_status` counts as in progress unless told otherwise.
+const DEFAULT_WIP_TAG: &str = "in-progress";
+const WIP_LIMIT_ENV: &str = "TODO_WIP_LIMIT";
+const DEFAULT_TAG_PAIRS: usize = 10;
+const MAX_TAG_PAIRS: usize = 100;
+/// How often `start_server` looks for reminders that have come due.
+const REMINDER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Tauri event emitted for each todo whose reminder comes due.
+const REMINDER_EVENT: &str = "reminder-due";
+/// Name `diff_snapshots` takes for the list as it is now.
+const CURRENT_SNAPSHOT: &str = "current";
+/// `format` of the bundles `export_snapshot` writes and `import_todos` reads.
+const BUNDLE_FORMAT: &str = "tauri-todo-mcp/snapshot";
+const BUNDLE_VERSION: u64 = 1;
+/// Arguments that hold arrays of todo ids.
+const ID_ARRAYS: [&str; 2] = ["depends_on", "ids"];
+const INSTRUCTIONS_ENV: &str = "TODO_INSTRUCTIONS";
+const INSTRUCTIONS_FILE_ENV: &str = "TODO_INSTRUCTIONS_FILE";
+const WEBHOOK_URL_ENV: &str = "TODO_WEBHOOK_URL";
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+const STORE_RETRIES_ENV: &str = "TODO_STORE_RETRIES";
+const DEFAULT_STORE_RETRIES: u32 = 3;
+const STORE_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+const MUTATING_TOOLS: &[&str] = &[
+    "add_todo",
+    "remove_todo",
+    "update_todo",
+    "set_due_date",
+    "reschedule_overdue",
+    "set_priority",
+    "add_subtask",
+    "set_subtask_done",
+    "toggle_all_subtasks",
+    "set_notes",
+    "set_tags",
+    "retag",
+    "remove_tag_everywhere",
+    "set_estimate",
+    "log_time",
+    "create_snapshot",
+    "restore_snapshot",
+    "revert_todo",
+    "split_todo",
+    "compact",
+    "add_attachment",
+    "remove_attachment",
+    "add_reminder",
+    "remove_reminder",
+    "dismiss_reminders",
+    "create_list",
+    "normalize_todos",
+    "set_dependencies",
+    "complete_matching",
+    "bump_todo",
+    "auto_prioritize",
+    "set_list_settings",
+    "set_focus",
+    "clear_focus",
+    "upsert_todo",
+    "promote_subtask",
+    "prioritize_matching",
+    "set_assignee",
+    "archive_list",
+    "restore_list",
+    "move_todos",
+    "reopen_recent",
+    "import_todos",
+    "set_list",
+    "tag_matching",
+    "set_status",
+    "import_outline",
+    "set_reminder_schedule",
+];
+
+/// Tools a host should confirm before running: those that delete todos or
+/// replace them wholesale, move todos between lists, or write many todos in
+/// one call. Single-field edits of one todo are not included. Their schemas
+/// carry `"destructive": true` so hosts can ask the user first.
+const DESTRUCTIVE_TOOLS: &[&str] = &[
+    // Delete todos or replace them wholesale.
+    "remove_todo",
+    "split_todo",
+    "upsert_todo",
+    "restore_snapshot",
+    // Move todos between lists.
+    "archive_list",
+    "restore_list",
+    "move_todos",
+    "set_list",
+    // Write many todos in one call.
+    "reschedule_overdue",
+    "retag",
+    "remove_tag_everywhere",
+    "compact",
+    "normalize_todos",
+    "complete_matching",
+    "auto_prioritize",
+    "prioritize_matching",
+    "reopen_recent",
+    "import_todos",
+    "tag_matching",
+    "import_outline",
+];
+
+async fn send_webhook(client: reqwest::Client, url: String, payload: Value) {
+    let mut delay = Duration::from_millis(500);
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        let response = client
+            .post(&url)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match response {
+            Ok(_) => return,
+            Err(e) if attempt < WEBHOOK_ATTEMPTS => {
+                eprintln!("tauri-todo-mcp: webhook attempt {} failed: {}", attempt, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => eprintln!("tauri-todo-mcp: giving up on webhook: {}", e),
+        }
+    }
+}
+
+/// Reads an operator-provided replacement for the server instructions, preferring
+/// `TODO_INSTRUCTIONS_FILE` over `TODO_INSTRUCTIONS`. Unreadable or blank
+/// overrides are reported on stderr and ignored.
+fn load_instructions() -> Option<String> {
+    let (source, text) = match std::env::var(INSTRUCTIONS_FILE_ENV) {
+        Ok(path) => match std::fs::read_to_string(&path) {
+            Ok(text) => (INSTRUCTIONS_FILE_ENV, text),
+            Err(e) => {
+                eprintln!(
+                    "tauri-todo-mcp: ignoring {}={}: {}",
+                    INSTRUCTIONS_FILE_ENV, path, e
+                );
+                return None;
+            }
+        },
+        Err(_) => (INSTRUCTIONS_ENV, std::env::var(INSTRUCTIONS_ENV).ok()?),
+    };
+    let text = text.trim();
+    if text.is_empty() {
+        eprintln!("tauri-todo-mcp: ignoring empty {}", source);
+        return None;
+    }
+    Some(text.to_string())
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Where `store.json` lives: directly in the app data directory.
+fn store_file_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, ToolError> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+    Ok(dir.join(STORE_PATH))
+}
+
+/// Returned by `TodoRouter::lock_store`; dropping it releases both locks.
+struct StoreGuard<'a> {
+    _process: std::sync::MutexGuard<'a, ()>,
+    /// Closing the file releases its lock.
+    _file: Option<std::fs::File>,
+}
+
+/// The process-wide lock behind `TodoRouter::write_lock`.
+fn store_write_lock() -> Arc<Mutex<()>> {
+    static LOCK: std::sync::OnceLock<Arc<Mutex<()>>> = std::sync::OnceLock::new();
+    LOCK.get_or_init(Arc::default).clone()
+}
+
+/// How many times a failed read or write of `store.json` is retried, from
+/// `TODO_STORE_RETRIES`. Read once, since the free store helpers have no
+/// router to carry it.
+fn store_retries() -> u32 {
+    static RETRIES: std::sync::OnceLock<u32> = std::sync::OnceLock::new();
+    *RETRIES.get_or_init(|| {
+        std::env::var(STORE_RETRIES_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_STORE_RETRIES)
+    })
+}
+
+/// Failures that another process can cause by holding or rewriting the file
+/// at the same moment, such as a lock or a half-written file, which fails to
+/// deserialize. A read-only store or a full disk won't get better by waiting.
+fn is_transient(e: &tauri_plugin_store::Error) -> bool {
+    match e {
+        tauri_plugin_store::Error::Io(io) => io_failure_reason(io.kind()).is_none(),
+        tauri_plugin_store::Error::Deserialize(_) => true,
+        _ => false,
+    }
+}
+
+/// Runs `operation` on the store, retrying transient failures with
+/// exponential backoff. Each retry is logged to stderr.
+fn retry_store<T>(
+    operation: &str,
+    mut f: impl FnMut() -> tauri_plugin_store::Result<T>,
+) -> tauri_plugin_store::Result<T> {
+    let retries = store_retries();
+    let mut delay = STORE_RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                eprintln!(
+                    "tauri-todo-mcp: store {} failed, retrying ({}/{}): {}",
+                    operation, attempt, retries, e
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Re-reads `store` from disk, picking up writes from the webview.
+fn reload_store<R: Runtime>(store: &tauri_plugin_store::Store<R>) -> Result<(), ToolError> {
+    retry_store("reload", || store.reload()).map_err(|e| ToolError::ExecutionError(e.to_string()))
+}
+
+/// Writes `store` to disk. If that fails, the unsaved values are reloaded from
+/// disk so a later successful save doesn't persist them behind the caller's
+/// back, and the error says why nothing was saved.
+fn save_store<R: Runtime>(store: &tauri_plugin_store::Store<R>) -> Result<(), ToolError> {
+    retry_store("save", || store.save()).map_err(|e| {
+        let _ = store.reload();
+        let reason = match &e {
+            tauri_plugin_store::Error::Io(io) => io_failure_reason(io.kind()),
+            _ => None,
+        };
+        persist_error(reason, e)
+    })
+}
+
+fn io_failure_reason(kind: std::io::ErrorKind) -> Option<&'static str> {
+    match kind {
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => {
+            Some("the store is read-only")
+        }
+        std::io::ErrorKind::StorageFull => Some("the disk is full"),
+        _ => None,
+    }
+}
+
+fn persist_error(reason: Option<&str>, e: impl std::fmt::Display) -> ToolError {
+    match reason {
+        Some(reason) => {
+            ToolError::ExecutionError(format!("could not persist changes: {} ({})", reason, e))
+        }
+        None => ToolError::ExecutionError(format!("could not persist changes: {}", e)),
+    }
+}
+
+/// The default list lives under the original `todos` key so the webview keeps
+/// working unchanged; every other list gets its own `todos:{name}` key.
+fn list_key(list: &str) -> String {
+    if list == DEFAULT_LIST {
+        TODOS_KEY.to_string()
+    } else {
+        format!("{}{}", LIST_KEY_PREFIX, list)
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+fn end_of_today() -> DateTime<FixedOffset> {
+    let now = Local::now();
+    now.date_naive()
+        .and_hms_opt(23, 59, 59)
+        .and_then(|end| end.and_local_timezone(Local).single())
+        .unwrap_or(now)
+        .fixed_offset()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    midnight
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+}
+
+fn directed(ordering: Ordering, descending: bool) -> Ordering {
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+fn compare_missing_last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => directed(a.cmp(&b), descending),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn mark_destructive(mut tool: Tool) -> Tool {
+    if DESTRUCTIVE_TOOLS.contains(&tool.name.as_str()) {
+        tool.description
+            .push_str(" (destructive: confirm with the user first)");
+        if let Some(schema) = tool.input_schema.as_object_mut() {
+            schema.insert("destructive".to_string(), Value::Bool(true));
+        }
+    }
+    tool
+}
+
+/// How a todo is serialized. Optional fields are left out when unset.
+const TODO_SHAPE: &str = "{id, number, text, done, status, created_at, updated_at, due_date?, priority?, \
+    tags?, assignee?, subtasks?: [{text, done}], estimate_minutes?, spent_minutes?, notes?, attachments?, \
+    reminders?, reminder_schedule?: {every, starts_at}, depends_on?, completed_at?}";
+
+/// What `tool` puts in its text content. Must follow the serialization in
+/// `dispatch`.
+fn output_description(tool: &str) -> Option<String> {
+    let description = match tool {
+        "get_todos" | "open_todos" | "done_todos" | "sort_todos" | "query_todos" => format!(
+            "a JSON array of todos {TODO_SHAPE}, one such array per block when chunk_size is \
+            given, or {{todos, total, next_offset}} when offset or limit is given; with \
+            include_summary a plain-text summary comes first"
+        ),
+        "add_todo" => format!(
+            "the todo as JSON {TODO_SHAPE}, or {{todo, todos}} with the whole list when \
+            return_list is true"
+        ),
+        "set_due_date"
+        | "set_priority"
+        | "add_subtask"
+        | "set_subtask_done"
+        | "toggle_all_subtasks"
+        | "set_estimate"
+        | "log_time"
+        | "set_notes"
+        | "set_tags"
+        | "revert_todo"
+        | "add_attachment"
+        | "remove_attachment"
+        | "add_reminder"
+        | "remove_reminder"
+        | "dismiss_reminders"
+        | "set_assignee"
+        | "set_dependencies"
+        | "bump_todo"
+        | "set_list"
+        | "set_status"
+        | "set_reminder_schedule" => {
+            format!("the todo as JSON {TODO_SHAPE}")
+        }
+        "stale_todos" | "oldest_open" | "related_todos" | "split_todo" | "compact"
+        | "due_reminders" | "plan" | "due_on_weekday" | "needs_triage" | "find_by_id_prefix"
+        | "import_todos" | "done_today" => {
+            format!("a JSON array of todos {TODO_SHAPE}")
+        }
+        "remove_todo" | "update_todo" => format!(
+            "empty text, or {{todos: [{TODO_SHAPE}]}} with the whole list when return_list is true"
+        ),
+        "reset_metrics" => "empty text".to_string(),
+        "reschedule_overdue" => "{rescheduled}".to_string(),
+        "retag" | "remove_tag_everywhere" => "{affected}".to_string(),
+        "normalize_todos" | "auto_prioritize" => "{changed}".to_string(),
+        "agenda" | "summarize" => "plain text".to_string(),
+        "render_markdown" => "Markdown text".to_string(),
+        "export_table" => "a Markdown table".to_string(),
+        "export_ics" => "an iCalendar document".to_string(),
+        "workload" => "{open_todos, unestimated_todos, estimated_minutes, spent_minutes, \
+            remaining_minutes}"
+            .to_string(),
+        "list_todos_changed_since" => {
+            format!("{{todos: [{TODO_SHAPE}], deleted: [id], server_time}}")
+        }
+        "create_snapshot" => "{name, created_at, todos} where todos is a count".to_string(),
+        "list_snapshots" => "[{name, created_at, todos}] where todos is a count".to_string(),
+        "restore_snapshot" => "{restored, backup} with the snapshot names".to_string(),
+        "diff_snapshots" => format!(
+            "{{from, to, added: [{TODO_SHAPE}], removed: [{TODO_SHAPE}], \
+            changed: [{{id, fields: {{<field>: {{before, after}}}}}}]}}"
+        ),
+        "metrics" => "{<tool>: {count, average_ms, last_ms}}".to_string(),
+        "streak" => "{current, longest} in days".to_string(),
+        "create_list" => "a JSON array of list names".to_string(),
+        "get_lists" => {
+            "a JSON array of list names, or {lists, archived} with include_archived".to_string()
+        }
+        "archive_list" => "{archived, todos} where todos is a count".to_string(),
+        "move_todos" => "{moved: [id], not_found: [id]}".to_string(),
+        "import_outline" => "{todos, subtasks} with the counts created".to_string(),
+        "forecast" => "{open_todos, window_days, completed_in_window, per_day, estimated_days, \
+            estimated_date, explanation}"
+            .to_string(),
+        "export_snapshot" => format!(
+            "{{format, version, server, server_version, generated_at, list, \
+            todos: [{TODO_SHAPE}]}}"
+        ),
+        "schema_status" => "{schema_version, latest_version, pending, migrated_from}".to_string(),
+        "overloaded_days" => format!("[{{date, count, todos: [{TODO_SHAPE}]}}] by date"),
+        "restore_list" => "{restored, todos} where todos is a count".to_string(),
+        "store_stats" => {
+            "{path, size_bytes, todos, average_bytes_per_todo, warn_bytes, warning}".to_string()
+        }
+        "list_tags" => "{<tag>: count} in descending count order".to_string(),
+        "extract_links" => "{<id>: [url]}".to_string(),
+        "wip_status" => format!("{{tag, in_progress, limit, over_limit, todos: [{TODO_SHAPE}]}}"),
+        "tag_cooccurrence" => "[{tags: [tag, tag], count}] in descending count order".to_string(),
+        "complete_matching" | "prioritize_matching" | "reopen_recent" | "tag_matching" => {
+            format!("{{count, dry_run, todos: [{TODO_SHAPE}]}}")
+        }
+        "recent_activity" => format!("[{{kind: created|modified, at, todo: {TODO_SHAPE}}}]"),
+        "get_list_settings" | "set_list_settings" => "{default_due_offset?}".to_string(),
+        "store_info" => "{path, exists, size_bytes, storage, storage_path}".to_string(),
+        "todo_progress" => "{done, total, percent} of the subtasks".to_string(),
+        "set_focus" => format!("the focused todo as JSON {TODO_SHAPE}"),
+        "get_focus" => format!("the focused todo as JSON {TODO_SHAPE}, or null"),
+        "clear_focus" => "empty text".to_string(),
+        "created_histogram" => "{<YYYY-MM-DD>: count} in date order".to_string(),
+        "promote_subtask" => format!("the new todo as JSON {TODO_SHAPE}"),
+        "random_todo" => format!("a random open todo as JSON {TODO_SHAPE}, or null"),
+        "week_view" => {
+            format!("{{overdue: [{TODO_SHAPE}], <weekday>: [...]}} with the weekdays in week order")
+        }
+        "workload_by_assignee" => "[{assignee, open_todos, unestimated_todos, \
+            estimated_minutes, spent_minutes, remaining_minutes}] with a null assignee for \
+            unassigned todos, most open todos first"
+            .to_string(),
+        "most_overdue" => format!("the most overdue open todo as JSON {TODO_SHAPE}, or null"),
+        "list_ids" => "a JSON array of todo ids in list order".to_string(),
+        "check_due_date" => "{due_date, status: past|today|future, relative}".to_string(),
+        "upsert_todo" => format!("{{created, todo: {TODO_SHAPE}}}"),
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// Appends the result shape to the description, and records it under
+/// `returns` in the input schema for clients that read it programmatically.
+fn describe_output(mut tool: Tool) -> Tool {
+    if let Some(description) = output_description(&tool.name) {
+        tool.description
+            .push_str(&format!(" Returns {description}."));
+        if let Some(schema) = tool.input_schema.as_object_mut() {
+            schema.insert("returns".to_string(), Value::String(description));
+        }
+    }
+    tool
+}
+
+/// Moves completed todos after the open ones, keeping the relative order
+/// within each group.
+fn compact(todos: &mut [Todo]) {
+    todos.sort_by_key(|todo| todo.done);
+}
+
+/// Sorts `todos` by `key`, stably. Text is compared with the Unicode
+/// collation rules of `locale` (the root collation when omitted), so accented
+/// and Japanese text sort naturally. Todos missing a due date or creation
+/// time sort after those that have one.
+fn sort_todos(
+    todos: &mut [Todo],
+    key: &str,
+    descending: bool,
+    locale: Option<&str>,
+) -> Result<(), ToolError> {
+    match key {
+        "text" => {
+            let locale = match locale {
+                Some(tag) => tag
+                    .parse::<Locale>()
+                    .map_err(|_| ToolError::InvalidParameters("locale".to_string()))?,
+                None => Locale::UND,
+            };
+            let collator = Collator::try_new(&(&locale).into(), CollatorOptions::new())
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            todos.sort_by(|a, b| directed(collator.compare(&a.text, &b.text), descending));
+        }
+        "due_date" => todos.sort_by(|a, b| {
+            let due = |todo: &Todo| todo.due_date.as_deref().and_then(parse_date);
+            compare_missing_last(due(a), due(b), descending)
+        }),
+        "created_at" => todos.sort_by(|a, b| {
+            let created = |todo: &Todo| todo.created_at.as_deref().and_then(parse_date);
+            compare_missing_last(created(a), created(b), descending)
+        }),
+        "priority" => todos.sort_by(|a, b| directed(a.priority.cmp(&b.priority), descending)),
+        _ => return Err(ToolError::InvalidParameters("by".to_string())),
+    }
+    Ok(())
+}
+
+/// Orders open todos so every todo comes after the open todos it depends on,
+/// otherwise keeping list order. Dependencies on completed or missing todos
+/// count as satisfied. On a cycle, returns the ids that form it.
+fn plan(todos: Vec<Todo>) -> Result<Vec<Todo>, Vec<u64>> {
+    let mut pending: Vec<Todo> = todos.into_iter().filter(|todo| !todo.done).collect();
+    let open: HashSet<u64> = pending.iter().map(|todo| todo.id).collect();
+    let mut planned: HashSet<u64> = HashSet::new();
+    let mut ordered = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|todo| {
+            todo.depends_on
+                .iter()
+                .all(|dependency| !open.contains(dependency) || planned.contains(dependency))
+        });
+        match ready {
+            Some(index) => {
+                let todo = pending.remove(index);
+                planned.insert(todo.id);
+                ordered.push(todo);
+            }
+            None => {
+                // Every pending todo waits on another pending todo, so following
+                // those edges must eventually revisit a todo.
+                let waiting_on = |todo: &Todo| {
+                    todo.depends_on.iter().copied().find(|dependency| {
+                        open.contains(dependency) && !planned.contains(dependency)
+                    })
+                };
+                let mut path = vec![pending[0].id];
+                let mut next = waiting_on(&pending[0]);
+                while let Some(id) = next {
+                    if let Some(start) = path.iter().position(|&seen| seen == id) {
+                        return Err(path.split_off(start));
+                    }
+                    path.push(id);
+                    next = pending
+                        .iter()
+                        .find(|todo| todo.id == id)
+                        .and_then(waiting_on);
+                }
+                return Err(path);
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+/// Todo ids are creation times in milliseconds. A clock set before 1970 would
+/// wrap around to huge ids, so it is reported instead.
+fn timestamp_id(now: DateTime<Utc>) -> Result<u64, ToolError> {
+    u64::try_from(now.timestamp_millis()).map_err(|_| {
+        ToolError::ExecutionError(format!(
+            "the system clock reads {}, before 1970; cannot assign a todo id",
+            now.to_rfc3339()
+        ))
+    })
+}
+
+/// The id for a todo added to `todos` at `now`: its creation time, or one
+/// past the highest id when another todo was added in the same millisecond.
+fn next_id(todos: &[Todo], now: DateTime<Utc>) -> Result<u64, ToolError> {
+    let id = timestamp_id(now)?;
+    match todos.iter().map(|todo| todo.id).max() {
+        Some(max) if max >= id => max.checked_add(1).ok_or_else(ids_exhausted),
+        _ => Ok(id),
+    }
+}
+
+/// The first of `count` consecutive ids for todos added to `todos` together;
+/// see `next_id`.
+fn next_ids(todos: &[Todo], now: DateTime<Utc>, count: usize) -> Result<u64, ToolError> {
+    let first = next_id(todos, now)?;
+    u64::try_from(count.saturating_sub(1))
+        .ok()
+        .and_then(|last| first.checked_add(last))
+        .map(|_| first)
+        .ok_or_else(ids_exhausted)
+}
+
+fn ids_exhausted() -> ToolError {
+    ToolError::ExecutionError("todo ids are exhausted".to_string())
+}
+
+/// Parses offsets like `+3 days`, `2d`, `12h`, `1 week` or `30 minutes`.
+/// Only positive offsets are accepted.
+fn parse_due_offset(expression: &str) -> Option<chrono::Duration> {
+    let expression = expression.trim();
+    let expression = expression.strip_prefix('+').unwrap_or(expression);
+    let split = expression
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(expression.len());
+    let (amount, unit) = expression.split_at(split);
+    let amount: i64 = amount.parse().ok().filter(|amount| *amount > 0)?;
+    match unit.trim().to_ascii_lowercase().as_str() {
+        "m" | "min" | "mins" | "minute" | "minutes" => chrono::Duration::try_minutes(amount),
+        "h" | "hour" | "hours" => chrono::Duration::try_hours(amount),
+        "d" | "day" | "days" => chrono::Duration::try_days(amount),
+        "w" | "week" | "weeks" => chrono::Duration::try_weeks(amount),
+        _ => None,
+    }
+}
+
+/// Describes how far `distance` reaches from now in the largest whole unit,
+/// such as `in 3 days` or `2 hours ago`.
+fn describe_distance(distance: chrono::Duration) -> String {
+    let minutes = distance.num_minutes().unsigned_abs();
+    let (amount, unit) = if minutes >= 60 * 24 {
+        (minutes / (60 * 24), "day")
+    } else if minutes >= 60 {
+        (minutes / 60, "hour")
+    } else if minutes >= 1 {
+        (minutes, "minute")
+    } else {
+        return "now".to_string();
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    if distance < chrono::Duration::zero() {
+        format!("{} {}{} ago", amount, unit, plural)
+    } else {
+        format!("in {} {}{}", amount, unit, plural)
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// Turns the ids in a tool result into strings for `TODO_ID_FORMAT=string`:
+/// `id` fields and the id arrays such as `depends_on`, at any depth.
+/// `ids` is set when `value` itself holds ids.
+fn stringify_ids(value: &mut Value, ids: bool) {
+    match value {
+        Value::Number(number) if ids => *value = Value::String(number.to_string()),
+        Value::Array(values) => {
+            for value in values {
+                stringify_ids(value, ids);
+            }
+        }
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                stringify_ids(
+                    value,
+                    matches!(
+                        key.as_str(),
+                        "id" | "depends_on" | "deleted" | "moved" | "not_found"
+                    ),
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The number `number_todos` would hand out next.
+fn next_number(todos: &[Todo]) -> u64 {
+    todos
+        .iter()
+        .filter_map(|todo| todo.number)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// Gives every todo without a `number`, or with one already taken earlier in
+/// the list, the next number after the highest in use. Numbers are thus
+/// unique within a list; a todo moved in from another list is renumbered on
+/// collision. Once the todo with the highest number is removed, that number
+/// may be handed out again.
+fn number_todos(todos: &mut [Todo]) {
+    let mut next = next_number(todos);
+    let mut seen = HashSet::new();
+    for todo in todos.iter_mut() {
+        match todo.number {
+            Some(number) if seen.insert(number) => {}
+            _ => {
+                todo.number = Some(next);
+                seen.insert(next);
+                next += 1;
+            }
+        }
+    }
+}
+
+/// Parses a todo reference such as `#42`.
+fn parse_reference(reference: &str) -> Option<u64> {
+    reference.trim().strip_prefix('#')?.parse().ok()
+}
+
+/// The schema of the `filter` object of the tools that select todos the way
+/// `query_todos` does.
+fn filter_schema(description: &str) -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "done": {
+                "type": "boolean",
+                "description": "Only todos with this done state"
+            },
+            "tags": {
+                "type": "array",
+                "items": {
+                    "type": "string"
+                },
+                "description": "Only todos carrying every one of these tags"
+            },
+            "priority": {
+                "type": "string",
+                "enum": ["low", "medium", "high"],
+                "description": "Only todos with this priority"
+            },
+            "status": {
+                "type": "string",
+                "enum": ["todo", "in_progress", "blocked", "done"],
+                "description": "Only todos with this status"
+            },
+            "text_contains": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Only todos whose text contains this, ignoring case"
+            },
+            "due_before": {
+                "type": "string",
+                "format": "date-time",
+                "description": "Only todos due strictly before this RFC3339 timestamp"
+            }
+        },
+        "description": description
+    })
+}
+
+/// Lets the `id` of every tool be given as a string or a `#42` reference
+/// too, and the ids in `depends_on` and `ids` as strings.
+fn accept_references(mut tool: Tool) -> Tool {
+    let id = tool
+        .input_schema
+        .get_mut("properties")
+        .and_then(|properties| properties.get_mut("id"))
+        .and_then(|id| id.as_object_mut());
+    if let Some(id) = id {
+        if id.get("type") == Some(&Value::from("integer")) {
+            id.insert("type".to_string(), serde_json::json!(["integer", "string"]));
+            let description = id
+                .get("description")
+                .and_then(|description| description.as_str())
+                .unwrap_or("Todo id");
+            let description = format!(
+                "{}, as a number or string, or the todo's reference such as \"#42\"",
+                description
+            );
+            id.insert("description".to_string(), Value::String(description));
+        }
+    }
+    for key in ID_ARRAYS {
+        let items = tool
+            .input_schema
+            .pointer_mut(&format!("/properties/{}/items", key))
+            .and_then(|items| items.as_object_mut());
+        if let Some(items) = items {
+            items.insert("type".to_string(), serde_json::json!(["integer", "string"]));
+        }
+    }
+    tool
+}
+
+/// The fields that differ between two versions of a todo, ignoring the
+/// edit history.
+fn changed_fields(before: &Todo, after: &Todo) -> Result<BTreeMap<String, Value>, ToolError> {
+    let to_map = |todo: &Todo| match serde_json::to_value(todo) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Ok(serde_json::Map::new()),
+        Err(e) => Err(ToolError::ExecutionError(e.to_string())),
+    };
+    let before = to_map(before)?;
+    let after = to_map(after)?;
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    Ok(keys
+        .into_iter()
+        .filter(|key| *key != "history" && before.get(*key) != after.get(*key))
+        .map(|key| {
+            let change = serde_json::json!({
+                "before": before.get(key).cloned().unwrap_or(Value::Null),
+                "after": after.get(key).cloned().unwrap_or(Value::Null),
+            });
+            (key.clone(), change)
+        })
+        .collect())
+}
+
+/// Parses an outline where unindented lines are todos and indented lines
+/// are subtasks of the todo above. A leading `-` or `*` bullet is dropped,
+/// and `[x]` (or `[ ]`) after it marks the item done (or open). Blank lines
+/// are skipped.
+fn parse_outline(text: &str) -> Result<Vec<Todo>, ToolError> {
+    let mut todos: Vec<Todo> = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let item = line.trim();
+        if item.is_empty() {
+            continue;
+        }
+        let item = item
+            .strip_prefix("- ")
+            .or_else(|| item.strip_prefix("* "))
+            .unwrap_or(item)
+            .trim_start();
+        let (done, item) = if let Some(item) = item
+            .strip_prefix("[x]")
+            .or_else(|| item.strip_prefix("[X]"))
+        {
+            (true, item.trim_start())
+        } else {
+            (false, item.strip_prefix("[ ]").unwrap_or(item).trim_start())
+        };
+        if item.is_empty() {
+            continue;
+        }
+        let text = item.to_string();
+        if line.starts_with(char::is_whitespace) {
+            let parent = todos.last_mut().ok_or_else(|| {
+                ToolError::InvalidParameters(format!(
+                    "text: line {} is indented but no todo comes before it",
+                    number + 1
+                ))
+            })?;
+            parent.subtasks.push(Subtask { text, done });
+        } else {
+            todos.push(Todo {
+                text,
+                done,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(todos)
+}
+
+/// The `http://` and `https://` URLs in `text`, in order. A URL ends at
+/// whitespace; trailing punctuation and closing brackets without a matching
+/// opening one are left out, so prose and Markdown links read naturally.
+fn find_links(text: &str) -> Vec<String> {
+    let mut links = vec![];
+    let mut rest = text;
+    while let Some(start) = ["http://", "https://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+            .unwrap_or(candidate.len());
+        let mut link = &candidate[..end];
+        loop {
+            let trimmed = link.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'']);
+            let unbalanced = |open: char, close: char| {
+                trimmed.ends_with(close)
+                    && trimmed.matches(close).count() > trimmed.matches(open).count()
+            };
+            let trimmed = if unbalanced('(', ')') || unbalanced('[', ']') {
+                &trimmed[..trimmed.len() - 1]
+            } else {
+                trimmed
+            };
+            if trimmed == link {
+                break;
+            }
+            link = trimmed;
+        }
+        if !["http://", "https://"].contains(&link) {
+            links.push(link.to_string());
+        }
+        rest = &candidate[end..];
+    }
+    links
+}
+
+fn find_todo(todos: &mut [Todo], id: u64) -> Result<&mut Todo, ToolError> {
+    todos
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or_else(|| ToolError::InvalidParameters(format!("todo {} not found", id)))
+}
+
+/// The batch limit a `TODO_MAX_BATCH` value sets; unset, unparsable and
+/// zero values keep the default.
+fn parse_max_batch(value: Option<&str>) -> usize {
+    value
+        .and_then(|max| max.trim().parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(DEFAULT_MAX_BATCH)
+}
+
+/// The backup interval a `TODO_BACKUP_INTERVAL_MINUTES` value sets. Zero,
+/// unparsable values and intervals too long for the timer disable backups.
+fn parse_backup_interval(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|minutes| minutes.trim().parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .and_then(|minutes| minutes.checked_mul(60))
+        .map(Duration::from_secs)
+        .filter(|interval| Instant::now().checked_add(*interval).is_some())
+}
+
+/// Parses tool arguments into `T`. Errors name the offending field, such as
+/// `id: invalid type: string "3", expected u64`, or say which one is missing.
+fn parse_args<T: serde::de::DeserializeOwned>(arguments: &Value) -> Result<T, ToolError> {
+    let empty = Value::Object(serde_json::Map::new());
+    let arguments = if arguments.is_null() {
+        &empty
+    } else {
+        arguments
+    };
+    serde_path_to_error::deserialize(arguments).map_err(|e| {
+        let path = e.path().to_string();
+        let error = e.into_inner();
+        if path == "." {
+            ToolError::InvalidParameters(error.to_string())
+        } else {
+            ToolError::InvalidParameters(format!("{}: {}", path, error))
+        }
+    })
+}
+
+/// Like `String`, but rejects the empty string, as todo text must not be
+/// empty.
+fn non_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    if text.is_empty() {
+        return Err(serde::de::Error::invalid_length(0, &"a non-empty string"));
+    }
+    Ok(text)
+}
+
+/// An optional count that must be at least 1 when given.
+fn positive<'de, D, N>(deserializer: D) -> Result<Option<N>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    N: Deserialize<'de> + Default + PartialEq,
+{
+    let count = Option::<N>::deserialize(deserializer)?;
+    if count == Some(N::default()) {
+        return Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Unsigned(0),
+            &"at least 1",
+        ));
+    }
+    Ok(count)
+}
+
+/// A string with surrounding whitespace removed that must not be empty.
+fn trimmed<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let text = String::deserialize(deserializer)?.trim().to_string();
+    if text.is_empty() {
+        return Err(serde::de::Error::invalid_length(0, &"a non-blank string"));
+    }
+    Ok(text)
+}
+
+/// At least one string, each read like `trimmed`.
+fn text_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let texts = Vec::<String>::deserialize(deserializer)?;
+    if texts.is_empty() {
+        return Err(serde::de::Error::invalid_length(0, &"at least one text"));
+    }
+    texts
+        .into_iter()
+        .map(|text| trimmed(serde::de::value::StringDeserializer::new(text)))
+        .collect()
+}
+
+/// Like `trimmed`, with null for no string.
+fn optional_trimmed<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|text| trimmed(serde::de::value::StringDeserializer::new(text)))
+        .transpose()
+}
+
+/// Tags, each one trimmed and non-blank, with duplicates dropped. Null is no
+/// tags.
+fn tag_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(optional_tag_list(deserializer)?.unwrap_or_default())
+}
+
+/// Like `tag_list`, with null for no change.
+fn optional_tag_list<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<String>>, D::Error> {
+    let Some(values) = Option::<Vec<String>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let mut tags: Vec<String> = vec![];
+    for tag in values {
+        let tag = trimmed(serde::de::value::StringDeserializer::new(tag))?;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(Some(tags))
+}
+
+/// A timestamp in any format `parse_date` accepts.
+fn date<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<FixedOffset>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_date(&value).ok_or_else(|| {
+        serde::de::Error::invalid_value(serde::de::Unexpected::Str(&value), &"an RFC3339 date")
+    })
+}
+
+/// Like `date`, with null for no date.
+fn optional_date<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(value) => date(serde::de::value::StringDeserializer::new(value)).map(Some),
+    }
+}
+
+/// A calendar day such as `2024-05-01`, with null for no day.
+fn optional_day<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDate>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&value),
+                &"a YYYY-MM-DD date",
+            )
+        })
+}
+
+/// A UTC offset such as `+09:00`, `Z` or `UTC`, with null for the server's.
+fn utc_offset<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<FixedOffset>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    if value.eq_ignore_ascii_case("utc") || value == "Z" {
+        return Ok(Some(Utc.fix()));
+    }
+    value.parse::<FixedOffset>().map(Some).map_err(|_| {
+        serde::de::Error::invalid_value(serde::de::Unexpected::Str(&value), &"a UTC offset")
+    })
+}
+
+/// A weekday name such as `monday` or `Mon`.
+fn weekday<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Weekday, D::Error> {
+    let value = trimmed(deserializer)?;
+    value.parse::<Weekday>().map_err(|_| {
+        serde::de::Error::invalid_value(serde::de::Unexpected::Str(&value), &"a weekday")
+    })
+}
+
+/// A `default_due_offset` such as `+3 days`, trimmed, or null to clear it.
+fn due_offset<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(Some(None));
+    };
+    if parse_due_offset(&value).is_none() {
+        return Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Str(&value),
+            &"an offset like +3 days, 12h or 1 week",
+        ));
+    }
+    Ok(Some(Some(value.trim().to_string())))
+}
+
+/// At least one of `TABLE_COLUMNS`, with null for all of them.
+fn table_columns<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    let Some(columns) = Option::<Vec<String>>::deserialize(deserializer)? else {
+        return Ok(all_table_columns());
+    };
+    if columns.is_empty() {
+        return Err(serde::de::Error::invalid_length(0, &"at least one column"));
+    }
+    if let Some(column) = columns
+        .iter()
+        .find(|column| !TABLE_COLUMNS.contains(&column.as_str()))
+    {
+        return Err(serde::de::Error::unknown_variant(column, TABLE_COLUMNS));
+    }
+    Ok(columns)
+}
+
+fn all_table_columns() -> Vec<String> {
+    TABLE_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .collect()
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn ics_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Folds a content line to at most 75 octets per line as required by RFC 5545.
+fn ics_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn ics_timestamp(date: DateTime<Utc>) -> String {
+    date.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn render_ics(todos: &[Todo]) -> String {
+    let stamp = ics_timestamp(Utc::now());
+    let mut ics = String::new();
+    ics_line(&mut ics, "BEGIN:VCALENDAR");
+    ics_line(&mut ics, "VERSION:2.0");
+    ics_line(&mut ics, "PRODID:-//tauri-todo-mcp//todo//EN");
+    for todo in todos {
+        ics_line(&mut ics, "BEGIN:VTODO");
+        ics_line(&mut ics, &format!("UID:{}@tauri-todo-mcp", todo.id));
+        ics_line(&mut ics, &format!("DTSTAMP:{}", stamp));
+        ics_line(&mut ics, &format!("SUMMARY:{}", ics_escape(&todo.text)));
+        if let Some(due) = todo.due_date.as_deref().and_then(parse_date) {
+            ics_line(&mut ics, &format!("DUE:{}", ics_timestamp(due.into())));
+        }
+        let status = if todo.done {
+            "COMPLETED"
+        } else {
+            "NEEDS-ACTION"
+        };
+        ics_line(&mut ics, &format!("STATUS:{}", status));
+        if let Some(completed) = todo.completed_at.as_deref().and_then(parse_date) {
+            ics_line(
+                &mut ics,
+                &format!("COMPLETED:{}", ics_timestamp(completed.into())),
+            );
+        }
+        if let Some(priority) = todo.priority {
+            let level = match priority {
+                Priority::High => 1,
+                Priority::Medium => 5,
+                Priority::Low => 9,
+            };
+            ics_line(&mut ics, &format!("PRIORITY:{}", level));
+        }
+        if let Some(notes) = &todo.notes {
+            ics_line(&mut ics, &format!("DESCRIPTION:{}", ics_escape(notes)));
+        }
+        ics_line(&mut ics, "END:VTODO");
+    }
+    ics_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+fn format_agenda_item(position: usize, todo: &Todo) -> String {
+    match &todo.due_date {
+        Some(due) => format!("{}. {} (id: {}, due {})", position, todo.text, todo.id, due),
+        None => format!("{}. {} (id: {})", position, todo.text, todo.id),
+    }
+}
+
+/// Shortens `text` to at most `max` characters, ending in an ellipsis when
+/// anything was cut.
+fn truncate_text(text: &str, max: Option<usize>) -> String {
+    match max {
+        Some(max) if text.chars().count() > max => {
+            let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+            truncated.push('…');
+            truncated
+        }
+        _ => text.to_string(),
+    }
+}
+
+fn render_markdown(list: &str, todos: &[Todo], max_text_len: Option<usize>) -> String {
+    let mut markdown = format!("# {}\n\n", list);
+    if todos.is_empty() {
+        markdown.push_str("_No todos._\n");
+    }
+    let check = |done: bool| if done { "x" } else { " " };
+    for todo in todos {
+        markdown.push_str(&format!(
+            "- [{}] {}\n",
+            check(todo.done),
+            truncate_text(&todo.text, max_text_len)
+        ));
+        for subtask in &todo.subtasks {
+            markdown.push_str(&format!(
+                "  - [{}] {}\n",
+                check(subtask.done),
+                truncate_text(&subtask.text, max_text_len)
+            ));
+        }
+    }
+    markdown
+}
+
+fn summarize(todos: &[Todo], max_text_len: Option<usize>) -> String {
+    let now = Utc::now();
+    let open: Vec<&Todo> = todos.iter().filter(|todo| !todo.done).collect();
+    let overdue = open.iter().filter(|todo| is_overdue(todo, now)).count();
+    let mut summary = format!(
+        "{} open, {} done, {} overdue.",
+        open.len(),
+        todos.len() - open.len(),
+        overdue
+    );
+    for (index, todo) in open.iter().enumerate() {
+        let todo = Todo {
+            text: truncate_text(&todo.text, max_text_len),
+            ..(*todo).clone()
+        };
+        summary.push('\n');
+        summary.push_str(&format_agenda_item(index + 1, &todo));
+    }
+    summary
+}
+
+/// Makes `text` safe inside a GitHub-flavored Markdown table cell.
+fn table_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+fn render_table(todos: &[Todo], columns: &[String]) -> String {
+    let header = |column: &str| match column {
+        "id" => "ID",
+        "text" => "Text",
+        "status" => "Status",
+        "priority" => "Priority",
+        _ => "Due",
+    };
+    let mut table = format!(
+        "| {} |\n|{}\n",
+        columns
+            .iter()
+            .map(|column| header(column))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        " --- |".repeat(columns.len())
+    );
+    for todo in todos {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match column.as_str() {
+                "id" => todo.id.to_string(),
+                "text" => table_cell(&todo.text),
+                "status" => todo.status.as_str().to_string(),
+                "priority" => todo
+                    .priority
+                    .map(|priority| priority.as_str().to_string())
+                    .unwrap_or_default(),
+                _ => todo.due_date.as_deref().map(table_cell).unwrap_or_default(),
+            })
+            .collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    table
+}
+
+fn is_overdue(todo: &Todo, now: DateTime<Utc>) -> bool {
+    todo.due_date
+        .as_deref()
+        .and_then(parse_date)
+        .is_some_and(|due| due < now)
+}
+
+impl<R: Runtime> TodoRouter<R> {
+    /// Fails only if the configured storage backend can't be opened.
+    pub fn new(app: AppHandle<R>) -> Result<Self, String> {
+        let json = JsonStorage::new(app.clone());
+        let storage: Arc<dyn TodoStorage> =
+            match std::env::var(STORAGE_ENV).unwrap_or_default().trim() {
+                "" | "json" => Arc::new(json),
+                "sqlite" => {
+                    let path = match std::env::var(SQLITE_PATH_ENV) {
+                        Ok(path) if !path.trim().is_empty() => PathBuf::from(path),
+                        _ => store_file_path(&app)
+                            .map_err(|e| e.to_string())?
+                            .with_file_name(SQLITE_FILE),
+                    };
+                    Arc::new(SqliteStorage::open(path, &json)?)
+                }
+                other => {
+                    return Err(format!(
+                        "unknown {} value {:?}; expected json or sqlite",
+                        STORAGE_ENV, other
+                    ))
+                }
+            };
+        let default_list = std::env::var(DEFAULT_LIST_ENV)
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_LIST.to_string());
+        Ok(Self {
+            app,
+            default_list,
+            auto_complete_parent: env_flag(AUTO_COMPLETE_PARENT_ENV),
+            auto_compact: env_flag(AUTO_COMPACT_ENV),
+            check_attachments: env_flag(CHECK_ATTACHMENTS_ENV),
+            max_lists: std::env::var(MAX_LISTS_ENV)
+                .ok()
+                .and_then(|max| max.trim().parse().ok())
+                .unwrap_or(DEFAULT_MAX_LISTS),
+            max_batch: parse_max_batch(std::env::var(MAX_BATCH_ENV).ok().as_deref()),
+            max_subtasks: std::env::var(MAX_SUBTASKS_ENV)
+                .ok()
+                .and_then(|max| max.trim().parse().ok())
+                .unwrap_or(DEFAULT_MAX_SUBTASKS),
+            wip_limit: std::env::var(WIP_LIMIT_ENV)
+                .ok()
+                .and_then(|limit| limit.trim().parse().ok()),
+            backup_interval: parse_backup_interval(
+                std::env::var(BACKUP_INTERVAL_ENV).ok().as_deref(),
+            ),
+            backup_keep: std::env::var(BACKUP_KEEP_ENV)
+                .ok()
+                .and_then(|keep| keep.trim().parse().ok())
+                .filter(|keep| *keep > 0)
+                .unwrap_or(DEFAULT_BACKUP_KEEP),
+            store_warn_bytes: std::env::var(STORE_WARN_BYTES_ENV)
+                .ok()
+                .and_then(|bytes| bytes.trim().parse().ok())
+                .unwrap_or(DEFAULT_STORE_WARN_BYTES),
+            allowed_tools: std::env::var(ALLOWED_TOOLS_ENV)
+                .ok()
+                .map(|tools| {
+                    tools
+                        .split(',')
+                        .map(|tool| tool.trim().to_string())
+                        .filter(|tool| !tool.is_empty())
+                        .collect::<HashSet<_>>()
+                })
+                .filter(|tools| !tools.is_empty())
+                .map(Arc::new),
+            pretty_json: env_flag(PRETTY_JSON_ENV),
+            string_ids: match std::env::var(ID_FORMAT_ENV).as_deref().map(str::trim) {
+                Ok("string") => true,
+                Ok("number") | Ok("") | Err(_) => false,
+                Ok(other) => {
+                    eprintln!(
+                        "tauri-todo-mcp: ignoring {}={}; expected number or string",
+                        ID_FORMAT_ENV, other
+                    );
+                    false
+                }
+            },
+            instructions: load_instructions(),
+            webhook_url: std::env::var(WEBHOOK_URL_ENV)
+                .ok()
+                .filter(|url| !url.trim().is_empty()),
+            http: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            metrics: Arc::default(),
+            write_lock: store_write_lock(),
+            migrated_from: Arc::default(),
+            storage,
+        })
+    }
+
+    /// Serializes a read-modify-write of the store with every other one. The
+    /// process-wide `write_lock` covers the stdio server and the webview's
+    /// `invoke_mcp_tool` calls, which share a process. The app and an
+    /// `mcp` process launched by a client share only the files, so an
+    /// exclusive lock on `LOCK_FILE` next to the store is taken as well. If
+    /// that file can't be opened or locked, as on a read-only filesystem
+    /// where every write fails anyway, only the process-wide lock is held.
+    fn lock_store(&self) -> StoreGuard<'_> {
+        let process = self
+            .write_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let file = store_file_path(&self.app).ok().and_then(|path| {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path.with_file_name(LOCK_FILE))
+                .ok()?;
+            file.lock().ok()?;
+            Some(file)
+        });
+        StoreGuard {
+            _process: process,
+            _file: file,
+        }
+    }
+
+    fn list_name(&self, arguments: &Value) -> Result<String, ToolError> {
+        let ListArgs { list } = parse_args(arguments)?;
+        match list {
+            None => Ok(self.default_list.clone()),
+            Some(name) if name.trim().is_empty() => {
+                Err(ToolError::InvalidParameters("list".to_string()))
+            }
+            Some(name) => Ok(name),
+        }
+    }
+
+    fn to_json<T: Serialize>(&self, value: &T) -> Result<String, ToolError> {
+        let json = if self.string_ids {
+            let mut value = serde_json::to_value(value)
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            stringify_ids(&mut value, false);
+            self.write_json(&value)
+        } else {
+            self.write_json(value)
+        };
+        json.map_err(|e| ToolError::ExecutionError(e.to_string()))
+    }
+
+    fn write_json<T: Serialize>(&self, value: &T) -> serde_json::Result<String> {
+        if self.pretty_json {
+            serde_json::to_string_pretty(value)
+        } else {
+            serde_json::to_string(value)
+        }
+    }
+
+    /// Lays out a tool result holding many todos. There are three shapes:
+    ///
+    /// - By default the whole list is a single JSON array, which every client
+    ///   can read.
+    /// - With `chunk_size`, for clients that can reassemble the result, the
+    ///   todos come as consecutive text blocks of at most that many todos, each
+    ///   block its own JSON array. They are serialized a block at a time, so no
+    ///   single string holds the whole list. The stdio transport still sends
+    ///   every block in one response message.
+    /// - With `offset`/`limit`, one page comes back with the `total` and the
+    ///   `next_offset` to continue from.
+    ///
+    /// With `include_summary`, a human-readable summary of all the todos comes
+    /// first as its own text block.
+    fn todos_response(
+        &self,
+        todos: Vec<Todo>,
+        arguments: &Value,
+    ) -> Result<Vec<Content>, ToolError> {
+        let PageArgs {
+            offset,
+            limit,
+            chunk_size,
+            include_summary,
+        } = parse_args(arguments)?;
+        let mut content = if include_summary {
+            vec![Content::text(summarize(&todos, None))]
+        } else {
+            vec![]
+        };
+        if let Some(chunk_size) = chunk_size {
+            if offset.is_some() || limit.is_some() {
+                return Err(ToolError::InvalidParameters(
+                    "chunk_size can't be combined with offset or limit".to_string(),
+                ));
+            }
+            for chunk in todos.chunks(chunk_size) {
+                content.push(Content::text(self.to_json(&chunk)?));
+            }
+            if todos.is_empty() {
+                content.push(Content::text(self.to_json(&todos)?));
+            }
+            return Ok(content);
+        }
+        if offset.is_none() && limit.is_none() {
+            content.push(Content::text(self.to_json(&todos)?));
+            return Ok(content);
+        }
+
+        let offset = offset.unwrap_or(0);
+        let total = todos.len();
+        let todos: Vec<Todo> = todos
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        let end = offset.saturating_add(todos.len());
+        let page = TodoPage {
+            todos,
+            total,
+            next_offset: (end < total).then_some(end),
+        };
+        content.push(Content::text(self.to_json(&page)?));
+        Ok(content)
+    }
+
+    /// Every writer (this server and the webview) rewrites the storage file
+    /// on save, so its mtime is the last time any list changed.
+    fn last_modified(&self) -> Option<DateTime<Utc>> {
+        let path = self.storage.path().ok()?;
+        let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+        Some(modified.into())
+    }
+
+    /// Opens the store once before serving so a missing plugin or unreadable
+    /// file is reported up front instead of as an error from every tool call.
+    fn check_store(&self) -> Result<(), String> {
+        let path = store_file_path(&self.app)
+            .map_err(|e| format!("could not resolve the todo store location: {}", e))?;
+        let store = self.app.store(STORE_PATH).map_err(|e| {
+            format!(
+                "could not open the todo store at {}: {} (is tauri-plugin-store registered and the app data directory accessible?)",
+                path.display(),
+                e
+            )
+        })?;
+        if path.exists() {
+            retry_store("reload", || store.reload()).map_err(|e| {
+                format!(
+                    "could not read the todo store at {}: {} (the file may be corrupt or unreadable)",
+                    path.display(),
+                    e
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    fn get_lists(&self) -> Result<Vec<String>, ToolError> {
+        let mut lists: Vec<String> = self
+            .storage
+            .lists()?
+            .into_iter()
+            .filter(|name| name != DEFAULT_LIST)
+            .collect();
+        if self.default_list != DEFAULT_LIST && !lists.contains(&self.default_list) {
+            lists.push(self.default_list.clone());
+        }
+        lists.sort();
+        lists.insert(0, DEFAULT_LIST.to_string());
+        Ok(lists)
+    }
+
+    /// Fails if `list` doesn't exist yet and creating it would exceed
+    /// `TODO_MAX_LISTS`.
+    fn check_list_limit(&self, list: &str) -> Result<(), ToolError> {
+        let lists = self.get_lists()?;
+        if lists.iter().any(|name| name == list) || lists.len() < self.max_lists {
+            return Ok(());
+        }
+        Err(ToolError::InvalidParameters(format!(
+            "cannot create list {}: the limit of {} lists has been reached",
+            list, self.max_lists
+        )))
+    }
+
+    fn create_list(&self, name: &str) -> Result<Vec<String>, ToolError> {
+        if self.get_lists()?.iter().any(|list| list == name) {
+            return Err(ToolError::InvalidParameters(format!(
+                "list {} already exists",
+                name
+            )));
+        }
+        self.save_or_create(name, vec![])?;
+        self.get_lists()
+    }
+
+    /// Returns the todos of `list` in their stored order: new todos are
+    /// appended unless `add_todo` is given a `position`, and every other
+    /// mutation edits or removes todos in place. Only tools whose purpose is
+    /// reordering (and `TODO_AUTO_COMPACT`) may change it.
+    fn get_todos(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.storage.load(list)?;
+        number_todos(&mut todos);
+        todos.iter_mut().for_each(Todo::sync_status);
+        Ok(todos)
+    }
+
+    /// Fails unless `list` exists. The default lists always do, even before
+    /// their first todo.
+    fn require_list(&self, list: &str) -> Result<(), ToolError> {
+        if list == DEFAULT_LIST || list == self.default_list || self.storage.has_list(list)? {
+            return Ok(());
+        }
+        Err(ToolError::InvalidParameters(format!(
+            "list {} not found",
+            list
+        )))
+    }
+
+    /// Replaces the todos of the existing `list`. Writes never create a list
+    /// as a side effect; see `save_or_create`.
+    fn save_todos(&self, list: &str, todos: Vec<Todo>) -> Result<(), ToolError> {
+        self.require_list(list)?;
+        self.write_todos(list, todos)
+    }
+
+    /// Like `save_todos`, but creates `list` if `TODO_MAX_LISTS` allows. Only
+    /// `create_list`, `restore_list` and `move_todos` with `create` use it.
+    fn save_or_create(&self, list: &str, todos: Vec<Todo>) -> Result<(), ToolError> {
+        if !self.storage.has_list(list)? {
+            self.check_list_limit(list)?;
+        }
+        self.write_todos(list, todos)
+    }
+
+    fn write_todos(&self, list: &str, todos: Vec<Todo>) -> Result<(), ToolError> {
+        let mut todos = todos;
+        number_todos(&mut todos);
+        todos.iter_mut().for_each(Todo::sync_status);
+        self.storage.save(list, &todos)
+    }
+
+    /// Stores `todo` as a new todo, assigning its id and timestamps. It is
+    /// inserted at `position` (clamped to the list bounds), or appended when
+    /// no position is given.
+    fn add_todo(&self, list: &str, todo: Todo, position: Option<usize>) -> Result<Todo, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let now = Utc::now();
+        let mut todo = Todo {
+            id: next_id(&todos, now)?,
+            number: Some(next_number(&todos)),
+            created_at: Some(now.to_rfc3339()),
+            updated_at: Some(now.to_rfc3339()),
+            completed_at: todo.done.then(|| now.to_rfc3339()),
+            ..todo
+        };
+        todo.sync_status();
+        let index = position.map_or(todos.len(), |position| position.min(todos.len()));
+        todos.insert(index, todo.clone());
+        self.save_todos(list, todos)?;
+        Ok(todo)
+    }
+
+    fn remove_todo(&self, list: &str, id: u64) -> Result<(), ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let len = todos.len();
+        todos.retain(|todo| todo.id != id);
+        if todos.len() == len {
+            return Ok(());
+        }
+        self.save_todos(list, todos)?;
+        self.record_deletions(list, &[id])
+    }
+
+    fn get_tombstones(&self, list: &str) -> Result<Vec<Tombstone>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let tombstones = store
+            .get(format!("{}{}", TOMBSTONES_KEY_PREFIX, list))
+            .and_then(|value| serde_json::from_value::<Vec<Tombstone>>(value).ok())
+            .unwrap_or_default();
+        Ok(tombstones)
+    }
+
+    /// Remembers deleted ids so incremental sync clients can drop them. Only
+    /// the most recent `MAX_TOMBSTONES` are kept.
+    fn record_deletions(&self, list: &str, ids: &[u64]) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let mut tombstones = self.get_tombstones(list)?;
+        let deleted_at = Utc::now().to_rfc3339();
+        tombstones.extend(ids.iter().map(|&id| Tombstone {
+            id,
+            deleted_at: deleted_at.clone(),
+        }));
+        if tombstones.len() > MAX_TOMBSTONES {
+            tombstones.drain(..tombstones.len() - MAX_TOMBSTONES);
+        }
+        store.set(
+            format!("{}{}", TOMBSTONES_KEY_PREFIX, list),
+            serde_json::to_value(tombstones)
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?,
+        );
+        save_store(&store)
+    }
+
+    fn get_snapshots(&self, list: &str) -> Result<Vec<Snapshot>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let snapshots = store
+            .get(format!("{}{}", SNAPSHOTS_KEY_PREFIX, list))
+            .and_then(|value| serde_json::from_value::<Vec<Snapshot>>(value).ok())
+            .unwrap_or_default();
+        Ok(snapshots)
+    }
+
+    fn save_snapshots(&self, list: &str, snapshots: Vec<Snapshot>) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        store.set(
+            format!("{}{}", SNAPSHOTS_KEY_PREFIX, list),
+            serde_json::to_value(snapshots)
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?,
+        );
+        save_store(&store)
+    }
+
+    fn create_snapshot(&self, list: &str, name: String) -> Result<Snapshot, ToolError> {
+        let mut snapshots = self.get_snapshots(list)?;
+        if snapshots.iter().any(|snapshot| snapshot.name == name) {
+            return Err(ToolError::InvalidParameters(format!(
+                "snapshot {} already exists",
+                name
+            )));
+        }
+        let snapshot = Snapshot {
+            name,
+            created_at: Utc::now().to_rfc3339(),
+            todos: self.get_todos(list)?,
+        };
+        snapshots.push(snapshot.clone());
+        self.save_snapshots(list, snapshots)?;
+        Ok(snapshot)
+    }
+
+    /// Restores the snapshot called `name`, first saving the current todos as
+    /// a new snapshot so the restore itself can be undone. Returns the name
+    /// of that backup snapshot.
+    fn restore_snapshot(&self, list: &str, name: &str) -> Result<String, ToolError> {
+        let mut snapshots = self.get_snapshots(list)?;
+        let todos = snapshots
+            .iter()
+            .find(|snapshot| snapshot.name == name)
+            .map(|snapshot| snapshot.todos.clone())
+            .ok_or_else(|| ToolError::InvalidParameters(format!("snapshot {} not found", name)))?;
+        let now = Utc::now();
+        let backup = format!("before-restore-{}", now.format("%Y%m%dT%H%M%S%.3fZ"));
+        snapshots.push(Snapshot {
+            name: backup.clone(),
+            created_at: now.to_rfc3339(),
+            todos: self.get_todos(list)?,
+        });
+        self.save_snapshots(list, snapshots)?;
+        self.save_todos(list, todos)?;
+        Ok(backup)
+    }
+
+    fn changed_since(
+        &self,
+        list: &str,
+        since: DateTime<FixedOffset>,
+    ) -> Result<Changes, ToolError> {
+        let server_time = Utc::now().to_rfc3339();
+        let todos = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| todo.changed_at().is_some_and(|changed| changed > since))
+            .collect();
+        let deleted = self
+            .get_tombstones(list)?
+            .into_iter()
+            .filter(|tombstone| parse_date(&tombstone.deleted_at).is_some_and(|at| at > since))
+            .map(|tombstone| tombstone.id)
+            .collect();
+        Ok(Changes {
+            todos,
+            deleted,
+            server_time,
+        })
+    }
+
+    fn update_todo(&self, list: &str, id: u64, text: String, done: bool) -> Result<(), ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let Some(todo) = todos
+            .iter_mut()
+            .find(|t| t.id == id && (t.text != text || t.done != done))
+        else {
+            return Ok(());
+        };
+        let before = todo.revision();
+        todo.text = text;
+        todo.done = done;
+        let completed = done && !before.done;
+        todo.record_revision(before);
+        todo.touch();
+        if completed && self.auto_compact {
+            compact(&mut todos);
+        }
+        self.save_todos(list, todos)?;
+        Ok(())
+    }
+
+    /// Applies `change` to a single todo in one read-modify-save cycle and
+    /// returns the updated todo. Nothing is saved if `change` fails.
+    fn modify_todo(
+        &self,
+        list: &str,
+        id: u64,
+        change: impl FnOnce(&mut Todo) -> Result<(), ToolError>,
+    ) -> Result<Todo, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let todo = find_todo(&mut todos, id)?;
+        let before = todo.revision();
+        change(todo)?;
+        let completed = todo.done && !before.done;
+        todo.record_revision(before);
+        todo.touch();
+        let todo = todo.clone();
+        if completed && self.auto_compact {
+            compact(&mut todos);
+        }
+        self.save_todos(list, todos)?;
+        Ok(todo)
+    }
+
+    /// Restores the most recent revision of a todo. Unlike other edits this
+    /// does not record a new revision, so repeated calls step further back.
+    fn revert_todo(&self, list: &str, id: u64) -> Result<Todo, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let todo = find_todo(&mut todos, id)?;
+        let revision = todo.history.pop().ok_or_else(|| {
+            ToolError::InvalidParameters(format!("todo {} has no history to revert", id))
+        })?;
+        todo.text = revision.text;
+        todo.done = revision.done;
+        todo.priority = revision.priority;
+        todo.touch();
+        let todo = todo.clone();
+        self.save_todos(list, todos)?;
+        Ok(todo)
+    }
+
+    fn set_due_date(
+        &self,
+        list: &str,
+        id: u64,
+        due_date: Option<String>,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.due_date = due_date;
+            Ok(())
+        })
+    }
+
+    fn set_priority(
+        &self,
+        list: &str,
+        id: u64,
+        priority: Option<Priority>,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.priority = priority;
+            todo.priority_auto = false;
+            Ok(())
+        })
+    }
+
+    fn sync_parent_done(&self, todo: &mut Todo) {
+        if self.auto_complete_parent && !todo.subtasks.is_empty() {
+            todo.done = todo.subtasks.iter().all(|subtask| subtask.done);
+        }
+    }
+
+    fn add_subtask(&self, list: &str, id: u64, text: String) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            if todo.subtasks.len() >= self.max_subtasks {
+                return Err(ToolError::InvalidParameters(format!(
+                    "todo {} already has the maximum of {} subtasks",
+                    id, self.max_subtasks
+                )));
+            }
+            todo.subtasks.push(Subtask { text, done: false });
+            self.sync_parent_done(todo);
+            Ok(())
+        })
+    }
+
+    fn set_subtask_done(
+        &self,
+        list: &str,
+        id: u64,
+        index: usize,
+        done: bool,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            let subtask = todo
+                .subtasks
+                .get_mut(index)
+                .ok_or_else(|| ToolError::InvalidParameters("index".to_string()))?;
+            subtask.done = done;
+            self.sync_parent_done(todo);
+            Ok(())
+        })
+    }
+
+    fn toggle_all_subtasks(&self, list: &str, id: u64, done: bool) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            if todo.subtasks.is_empty() {
+                return Err(ToolError::InvalidParameters(format!(
+                    "todo {} has no subtasks",
+                    id
+                )));
+            }
+            for subtask in todo.subtasks.iter_mut() {
+                subtask.done = done;
+            }
+            self.sync_parent_done(todo);
+            Ok(())
+        })
+    }
+
+    fn set_notes(&self, list: &str, id: u64, notes: Option<String>) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.notes = notes;
+            Ok(())
+        })
+    }
+
+    fn set_tags(&self, list: &str, id: u64, tags: Vec<String>) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.tags = tags;
+            Ok(())
+        })
+    }
+
+    /// Replaces `from` with `to` on every todo carrying it, or drops `from`
+    /// when `to` is `None`. Returns the number of todos changed.
+    fn retag(&self, list: &str, from: &str, to: Option<&str>) -> Result<usize, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let mut count = 0;
+        for todo in todos.iter_mut() {
+            let Some(index) = todo.tags.iter().position(|tag| tag == from) else {
+                continue;
+            };
+            match to {
+                Some(to) if !todo.tags.iter().any(|tag| tag == to) => {
+                    todo.tags[index] = to.to_string();
+                }
+                _ => {
+                    todo.tags.remove(index);
+                }
+            }
+            todo.touch();
+            count += 1;
+        }
+        if count > 0 {
+            self.save_todos(list, todos)?;
+        }
+        Ok(count)
+    }
+
+    fn set_estimate(&self, list: &str, id: u64, minutes: Option<u32>) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.estimate_minutes = minutes;
+            Ok(())
+        })
+    }
+
+    fn log_time(&self, list: &str, id: u64, minutes: u32) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.spent_minutes = todo.spent_minutes.saturating_add(minutes);
+            Ok(())
+        })
+    }
+
+    fn workload(&self, list: &str) -> Result<Workload, ToolError> {
+        let todos = self.get_todos(list)?;
+        let mut workload = Workload::default();
+        for todo in todos.iter().filter(|t| !t.done) {
+            workload.add(todo);
+        }
+        Ok(workload)
+    }
+
+    fn reschedule_overdue(
+        &self,
+        list: &str,
+        to: DateTime<FixedOffset>,
+    ) -> Result<usize, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let now = Utc::now();
+        let mut count = 0;
+        for todo in todos.iter_mut().filter(|t| !t.done && is_overdue(t, now)) {
+            todo.due_date = Some(to.to_rfc3339());
+            todo.touch();
+            count += 1;
+        }
+        if count > 0 {
+            self.save_todos(list, todos)?;
+        }
+        Ok(count)
+    }
+
+    /// Builds the agenda for `date` from open todos only: those due before the
+    /// day are listed as overdue, those due during the day as due, and any
+    /// remaining high-priority todos follow. Each section is ordered by due date.
+    fn agenda(&self, list: &str, date: NaiveDate) -> Result<String, ToolError> {
+        let todos = self.get_todos(list)?;
+        let start = start_of_day(date);
+        let end = date
+            .succ_opt()
+            .map(start_of_day)
+            .ok_or_else(|| ToolError::InvalidParameters("date".to_string()))?;
+
+        let mut overdue = vec![];
+        let mut due = vec![];
+        let mut high_priority = vec![];
+        for todo in todos.into_iter().filter(|t| !t.done) {
+            match todo.due_date.as_deref().and_then(parse_date) {
+                Some(due_date) if due_date < start => overdue.push((due_date, todo)),
+                Some(due_date) if due_date < end => due.push((due_date, todo)),
+                _ if todo.priority == Some(Priority::High) => high_priority.push(todo),
+                _ => {}
+            }
+        }
+        overdue.sort_by_key(|(due_date, _)| *due_date);
+        due.sort_by_key(|(due_date, _)| *due_date);
+
+        let sections = [
+            (
+                "Overdue",
+                overdue.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+            ),
+            ("Due today", due.into_iter().map(|(_, t)| t).collect()),
+            ("High priority", high_priority),
+        ];
+        let mut agenda = format!("Agenda for {}", date);
+        let mut position = 0;
+        for (title, todos) in sections.iter().filter(|(_, todos)| !todos.is_empty()) {
+            agenda.push_str(&format!("\n\n{}:", title));
+            for todo in todos {
+                position += 1;
+                agenda.push('\n');
+                agenda.push_str(&format_agenda_item(position, todo));
+            }
+        }
+        if position == 0 {
+            agenda.push_str("\n\nNothing on the agenda.");
+        }
+        Ok(agenda)
+    }
+
+    /// Returns the todos of `list` ordered by `key`, without changing the stored
+    /// order.
+    fn sort_todos(
+        &self,
+        list: &str,
+        key: &str,
+        descending: bool,
+        locale: Option<&str>,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        sort_todos(&mut todos, key, descending, locale)?;
+        Ok(todos)
+    }
+
+    /// Open todos not updated for more than `days` days, oldest first. Todos
+    /// without timestamps are never considered stale.
+    fn stale_todos(&self, list: &str, days: u32) -> Result<Vec<Todo>, ToolError> {
+        let cutoff = Utc::now()
+            .checked_sub_signed(chrono::Duration::days(days.into()))
+            .ok_or_else(|| ToolError::InvalidParameters("older_than_days".to_string()))?;
+        let mut stale: Vec<(DateTime<FixedOffset>, Todo)> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done)
+            .filter_map(|todo| todo.changed_at().map(|changed| (changed, todo)))
+            .filter(|(changed, _)| *changed < cutoff)
+            .collect();
+        stale.sort_by_key(|(changed, _)| *changed);
+        Ok(stale.into_iter().map(|(_, todo)| todo).collect())
+    }
+
+    fn record_call(&self, tool_name: &str, elapsed: Duration) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            let stats = metrics.entry(tool_name.to_string()).or_default();
+            stats.count += 1;
+            stats.total += elapsed;
+            stats.last = elapsed;
+        }
+    }
+
+    /// Call statistics per tool name since startup or the last reset.
+    fn metrics(&self) -> BTreeMap<String, ToolMetrics> {
+        self.metrics
+            .lock()
+            .map(|metrics| {
+                metrics
+                    .iter()
+                    .map(|(name, stats)| (name.clone(), ToolMetrics::from(stats)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn reset_metrics(&self) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics.clear();
+        }
+    }
+
+    /// Replaces todo `id` with one new todo per text, in place. The new todos
+    /// keep the original's tags and priority.
+    fn split_todo(&self, list: &str, id: u64, texts: Vec<String>) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let index = todos
+            .iter()
+            .position(|todo| todo.id == id)
+            .ok_or_else(|| ToolError::InvalidParameters(format!("todo {} not found", id)))?;
+        let original = todos.remove(index);
+        let now = Utc::now();
+        let first_id = next_ids(&todos, now, texts.len())?;
+        let first_number = next_number(&todos);
+        let parts: Vec<Todo> = texts
+            .into_iter()
+            .enumerate()
+            .map(|(offset, text)| Todo {
+                id: first_id + offset as u64,
+                number: Some(first_number + offset as u64),
+                text,
+                priority: original.priority,
+                tags: original.tags.clone(),
+                created_at: Some(now.to_rfc3339()),
+                updated_at: Some(now.to_rfc3339()),
+                ..Default::default()
+            })
+            .collect();
+        todos.splice(index..index, parts.iter().cloned());
+        self.save_todos(list, todos)?;
+        self.record_deletions(list, &[id])?;
+        Ok(parts)
+    }
+
+    /// Consecutive days with at least one completion, counted in `offset`. The
+    /// current streak survives until the end of a day without completions.
+    fn streak(&self, list: &str, offset: FixedOffset) -> Result<Streak, ToolError> {
+        let days: BTreeSet<NaiveDate> = self
+            .get_todos(list)?
+            .iter()
+            .filter(|todo| todo.done)
+            .filter_map(|todo| todo.completed_at.as_deref().and_then(parse_date))
+            .map(|completed| completed.with_timezone(&offset).date_naive())
+            .collect();
+
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for &day in &days {
+            run = match previous {
+                Some(previous) if previous.succ_opt() == Some(day) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(day);
+        }
+
+        let today = Utc::now().with_timezone(&offset).date_naive();
+        let mut day = if days.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut current = 0;
+        while let Some(date) = day.filter(|date| days.contains(date)) {
+            current += 1;
+            day = date.pred_opt();
+        }
+        Ok(Streak { current, longest })
+    }
+
+    fn compact_todos(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        compact(&mut todos);
+        self.save_todos(list, todos.clone())?;
+        Ok(todos)
+    }
+
+    /// Attaches a URI or file path. With `TODO_CHECK_ATTACHMENTS` set, file
+    /// paths must exist; anything containing `://` is treated as a URI and
+    /// never checked.
+    fn add_attachment(&self, list: &str, id: u64, attachment: String) -> Result<Todo, ToolError> {
+        if self.check_attachments
+            && !attachment.contains("://")
+            && !std::path::Path::new(&attachment).exists()
+        {
+            return Err(ToolError::InvalidParameters(format!(
+                "attachment {} does not exist",
+                attachment
+            )));
+        }
+        self.modify_todo(list, id, |todo| {
+            if !todo.attachments.contains(&attachment) {
+                todo.attachments.push(attachment);
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_attachment(
+        &self,
+        list: &str,
+        id: u64,
+        attachment: String,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            let len = todo.attachments.len();
+            todo.attachments.retain(|existing| *existing != attachment);
+            if todo.attachments.len() == len {
+                return Err(ToolError::InvalidParameters(format!(
+                    "todo {} has no attachment {}",
+                    id, attachment
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    fn query_todos(&self, list: &str, query: &TodoQuery) -> Result<Vec<Todo>, ToolError> {
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| query.matches(todo))
+            .collect())
+    }
+
+    fn add_reminder(
+        &self,
+        list: &str,
+        id: u64,
+        at: DateTime<FixedOffset>,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            if !todo
+                .reminders
+                .iter()
+                .any(|reminder| parse_date(reminder) == Some(at))
+            {
+                todo.reminders.push(at.to_rfc3339());
+                todo.reminders.sort_by_key(|reminder| parse_date(reminder));
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_reminder(
+        &self,
+        list: &str,
+        id: u64,
+        at: DateTime<FixedOffset>,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            let len = todo.reminders.len();
+            todo.reminders
+                .retain(|reminder| parse_date(reminder) != Some(at));
+            if todo.reminders.len() == len {
+                return Err(ToolError::InvalidParameters(format!(
+                    "todo {} has no reminder at {}",
+                    id,
+                    at.to_rfc3339()
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    /// Open todos with a reminder that has fired and not been dismissed.
+    fn due_reminders(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        let now = Utc::now();
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done && todo.reminder_due(now))
+            .collect())
+    }
+
+    /// Announces the reminders due at `now` that are not in `announced` and
+    /// returns the ones still due, to pass as `announced` next time.
+    fn announce_reminders(
+        &self,
+        now: DateTime<Utc>,
+        announced: &HashSet<AnnouncedReminder>,
+    ) -> HashSet<AnnouncedReminder> {
+        let mut due = HashSet::new();
+        let lists = match self.get_lists() {
+            Ok(lists) => lists,
+            Err(e) => {
+                eprintln!("tauri-todo-mcp: reminder check failed: {}", e);
+                return announced.clone();
+            }
+        };
+        for list in lists {
+            let todos = match self.get_todos(&list) {
+                Ok(todos) => todos,
+                Err(e) => {
+                    eprintln!("tauri-todo-mcp: reminder check failed for {}: {}", list, e);
+                    continue;
+                }
+            };
+            for todo in todos.into_iter().filter(|todo| !todo.done) {
+                let Some(at) = todo.latest_reminder(now) else {
+                    continue;
+                };
+                let key = (list.clone(), todo.id, at);
+                if !announced.contains(&key) {
+                    eprintln!(
+                        "tauri-todo-mcp: reminder due for {:?} in {}",
+                        todo.text, list
+                    );
+                    let payload = serde_json::json!({ "list": list, "todo": todo });
+                    if let Err(e) = self.app.emit(REMINDER_EVENT, payload) {
+                        eprintln!("tauri-todo-mcp: could not emit {}: {}", REMINDER_EVENT, e);
+                    }
+                }
+                due.insert(key);
+            }
+        }
+        due
+    }
+
+    /// Dismisses every reminder of todo `id` that has already fired.
+    fn dismiss_reminders(&self, list: &str, id: u64) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.reminders_dismissed_at = Some(Utc::now().to_rfc3339());
+            Ok(())
+        })
+    }
+
+    /// Applies `normalization` to every todo's text in one save and returns how
+    /// many todos changed.
+    fn normalize_todos(
+        &self,
+        list: &str,
+        normalization: Normalization,
+    ) -> Result<usize, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let mut changed = 0;
+        for todo in &mut todos {
+            let text = normalization.apply(&todo.text);
+            if text != todo.text {
+                let before = todo.revision();
+                todo.text = text;
+                todo.record_revision(before);
+                todo.touch();
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save_todos(list, todos)?;
+        }
+        Ok(changed)
+    }
+
+    fn set_dependencies(
+        &self,
+        list: &str,
+        id: u64,
+        depends_on: Vec<u64>,
+    ) -> Result<Todo, ToolError> {
+        let ids: HashSet<u64> = self.get_todos(list)?.iter().map(|todo| todo.id).collect();
+        if let Some(unknown) = depends_on
+            .iter()
+            .find(|dependency| !ids.contains(dependency))
+        {
+            return Err(ToolError::InvalidParameters(format!(
+                "todo {} not found",
+                unknown
+            )));
+        }
+        if depends_on.contains(&id) {
+            return Err(ToolError::InvalidParameters(format!(
+                "todo {} cannot depend on itself",
+                id
+            )));
+        }
+        self.modify_todo(list, id, |todo| {
+            todo.depends_on = depends_on;
+            todo.depends_on.sort_unstable();
+            todo.depends_on.dedup();
+            Ok(())
+        })
+    }
+
+    fn plan(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        plan(self.get_todos(list)?).map_err(|cycle| {
+            let ids: Vec<String> = cycle.iter().map(|id| id.to_string()).collect();
+            ToolError::ExecutionError(format!(
+                "dependency cycle between todos {}",
+                ids.join(" -> ")
+            ))
+        })
+    }
+
+    /// Size of the storage file and the number of todos across all lists. Only
+    /// the totals are returned, never the todos themselves.
+    fn store_stats(&self) -> Result<StoreStats, ToolError> {
+        let path = self.storage.path()?;
+        let size_bytes = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(ToolError::ExecutionError(e.to_string())),
+        };
+        let mut todos = 0;
+        for list in self.get_lists()? {
+            todos += self.get_todos(&list)?.len();
+        }
+        Ok(StoreStats {
+            path: path.display().to_string(),
+            size_bytes,
+            todos,
+            average_bytes_per_todo: size_bytes / todos.max(1) as u64,
+            warn_bytes: self.store_warn_bytes,
+            warning: size_bytes > self.store_warn_bytes,
+        })
+    }
+
+    /// Every distinct tag in `list` with the number of todos carrying it, most
+    /// used first and alphabetical among equal counts.
+    fn list_tags(&self, list: &str) -> Result<TagCounts, ToolError> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for todo in self.get_todos(list)? {
+            for tag in todo.tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(TagCounts(counts))
+    }
+
+    /// Marks every open todo matching `query` as done in one save. With
+    /// `dry_run`, nothing is saved and the todos that would change are returned.
+    fn complete_matching(
+        &self,
+        list: &str,
+        query: &TodoQuery,
+        dry_run: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let mut completed = Vec::new();
+        for todo in todos
+            .iter_mut()
+            .filter(|todo| !todo.done && query.matches(todo))
+        {
+            if !dry_run {
+                let before = todo.revision();
+                todo.done = true;
+                todo.record_revision(before);
+                todo.touch();
+            }
+            completed.push(todo.clone());
+        }
+        if !dry_run && !completed.is_empty() {
+            if self.auto_compact {
+                compact(&mut todos);
+            }
+            self.save_todos(list, todos)?;
+        }
+        Ok(completed)
+    }
+
+    /// The `count` most recently created or modified todos, newest first.
+    /// Todos without timestamps are left out.
+    fn recent_activity(&self, list: &str, count: usize) -> Result<Vec<Activity>, ToolError> {
+        let mut recent: Vec<(DateTime<FixedOffset>, Todo)> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter_map(|todo| todo.changed_at().map(|changed| (changed, todo)))
+            .collect();
+        recent.sort_by_key(|(changed, _)| std::cmp::Reverse(*changed));
+        Ok(recent
+            .into_iter()
+            .take(count)
+            .map(|(changed, todo)| {
+                let created = todo.created_at.as_deref().and_then(parse_date);
+                Activity {
+                    kind: if created == Some(changed) {
+                        "created"
+                    } else {
+                        "modified"
+                    },
+                    at: changed.to_rfc3339(),
+                    todo,
+                }
+            })
+            .collect())
+    }
+
+    fn is_allowed(&self, tool_name: &str) -> bool {
+        self.allowed_tools
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains(tool_name))
+    }
+
+    /// Applies `rules` to every open todo in one save and returns how many
+    /// changed. With `respect_manual`, todos whose priority the user set are
+    /// left alone.
+    fn auto_prioritize(
+        &self,
+        list: &str,
+        rules: PriorityRules,
+        respect_manual: bool,
+    ) -> Result<usize, ToolError> {
+        let now = Utc::now();
+        let mut todos = self.get_todos(list)?;
+        let mut changed = 0;
+        for todo in todos.iter_mut().filter(|todo| !todo.done) {
+            if respect_manual && todo.priority.is_some() && !todo.priority_auto {
+                continue;
+            }
+            let priority = Some(rules.priority(todo, now));
+            if todo.priority != priority || !todo.priority_auto {
+                let before = todo.revision();
+                todo.priority = priority;
+                todo.priority_auto = true;
+                todo.record_revision(before);
+                todo.touch();
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.save_todos(list, todos)?;
+        }
+        Ok(changed)
+    }
+
+    /// Open todos whose due date falls on `weekday` in `offset`, in list order.
+    fn due_on_weekday(
+        &self,
+        list: &str,
+        weekday: Weekday,
+        offset: FixedOffset,
+    ) -> Result<Vec<Todo>, ToolError> {
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done)
+            .filter(|todo| {
+                todo.due_date
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|due| due.with_timezone(&offset).weekday() == weekday)
+            })
+            .collect())
+    }
+
+    /// Open todos missing any of the selected attributes, in list order.
+    fn needs_triage(
+        &self,
+        list: &str,
+        missing_due_date: bool,
+        missing_priority: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done)
+            .filter(|todo| {
+                (missing_due_date && todo.due_date.is_none())
+                    || (missing_priority && todo.priority.is_none())
+            })
+            .collect())
+    }
+
+    fn get_list_settings(&self, list: &str) -> Result<ListSettings, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let settings = store
+            .get(format!("{}{}", SETTINGS_KEY_PREFIX, list))
+            .and_then(|value| serde_json::from_value::<ListSettings>(value).ok())
+            .unwrap_or_default();
+        Ok(settings)
+    }
+
+    fn save_list_settings(&self, list: &str, settings: &ListSettings) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        store.set(
+            format!("{}{}", SETTINGS_KEY_PREFIX, list),
+            serde_json::to_value(settings).map_err(|e| ToolError::ExecutionError(e.to_string()))?,
+        );
+        save_store(&store)
+    }
+
+    /// The due date a new todo gets when none is given, from the list's
+    /// `default_due_offset`.
+    fn default_due_date(&self, list: &str) -> Result<Option<String>, ToolError> {
+        let settings = self.get_list_settings(list)?;
+        Ok(settings
+            .default_due_offset
+            .as_deref()
+            .and_then(parse_due_offset)
+            .and_then(|offset| Utc::now().checked_add_signed(offset))
+            .map(|due| due.to_rfc3339()))
+    }
+
+    fn store_info(&self) -> Result<StoreInfo, ToolError> {
+        let path = store_file_path(&self.app)?;
+        let metadata = std::fs::metadata(&path).ok();
+        Ok(StoreInfo {
+            path: path.display().to_string(),
+            exists: metadata.is_some(),
+            size_bytes: metadata.map_or(0, |metadata| metadata.len()),
+            storage: self.storage.kind(),
+            storage_path: self.storage.path()?.display().to_string(),
+        })
+    }
+
+    fn todo_progress(&self, list: &str, id: u64) -> Result<Progress, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let todo = find_todo(&mut todos, id)?;
+        let total = todo.subtasks.len();
+        let done = todo.subtasks.iter().filter(|subtask| subtask.done).count();
+        let percent = (done * 100).checked_div(total).unwrap_or(0) as u32;
+        Ok(Progress {
+            done,
+            total,
+            percent,
+        })
+    }
+
+    /// Marks `id` as the todo currently being worked on in `list`, replacing
+    /// any previous focus.
+    fn set_focus(&self, list: &str, id: u64) -> Result<Todo, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let todo = find_todo(&mut todos, id)?.clone();
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        store.set(format!("{}{}", FOCUS_KEY_PREFIX, list), id);
+        save_store(&store)?;
+        Ok(todo)
+    }
+
+    /// The focused todo of `list`. A focus on a todo that has since been
+    /// removed is cleared; `get_focus` isn't a mutating tool, so it takes the
+    /// store lock itself for that write.
+    fn get_focus(&self, list: &str) -> Result<Option<Todo>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let key = format!("{}{}", FOCUS_KEY_PREFIX, list);
+        let Some(focus) = store.get(&key) else {
+            return Ok(None);
+        };
+        let todo = match focus.as_u64() {
+            Some(id) => self.get_todos(list)?.into_iter().find(|todo| todo.id == id),
+            None => None,
+        };
+        if todo.is_none() {
+            let _guard = self.lock_store();
+            reload_store(&store)?;
+            // Only drop the focus we looked at, in case it was just replaced.
+            if store.get(&key).as_ref() == Some(&focus) {
+                store.delete(&key);
+                save_store(&store)?;
+            }
+        }
+        Ok(todo)
+    }
+
+    fn clear_focus(&self, list: &str) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        if store.delete(format!("{}{}", FOCUS_KEY_PREFIX, list)) {
+            save_store(&store)?;
+        }
+        Ok(())
+    }
+
+    /// Number of todos created on each day from `from` to `to` (both inclusive
+    /// and optional), in `offset`'s calendar. Days without todos are left out.
+    fn created_histogram(
+        &self,
+        list: &str,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        offset: FixedOffset,
+    ) -> Result<BTreeMap<String, usize>, ToolError> {
+        let mut days: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        for todo in self.get_todos(list)? {
+            let Some(created) = todo.created_at.as_deref().and_then(parse_date) else {
+                continue;
+            };
+            let day = created.with_timezone(&offset).date_naive();
+            if from.is_some_and(|from| day < from) || to.is_some_and(|to| day > to) {
+                continue;
+            }
+            *days.entry(day).or_default() += 1;
+        }
+        Ok(days
+            .into_iter()
+            .map(|(day, count)| (day.format("%Y-%m-%d").to_string(), count))
+            .collect())
+    }
+
+    /// Updates the todo with `id` when it exists and creates it otherwise.
+    /// Fields of `upsert` left as `None` keep their current value on update. A new todo
+    /// keeps the requested id, so pushing the same state twice doesn't create
+    /// a duplicate; without an id it gets a fresh one like `add_todo`. The
+    /// boolean is true when the todo was created.
+    fn upsert_todo(&self, list: &str, upsert: Upsert) -> Result<(Todo, bool), ToolError> {
+        let Upsert {
+            id,
+            text,
+            done,
+            due_date,
+            priority,
+            tags,
+        } = upsert;
+        // Larger ids would lose precision in JavaScript clients.
+        if id.is_some_and(|id| !(1..=MAX_TODO_ID).contains(&id)) {
+            return Err(ToolError::InvalidParameters("id".to_string()));
+        }
+        let due_date = due_date.map(|due| due.to_rfc3339());
+        if let Some(id) = id {
+            if self.get_todos(list)?.iter().any(|todo| todo.id == id) {
+                let todo = self.modify_todo(list, id, |todo| {
+                    todo.text = text;
+                    if let Some(done) = done {
+                        todo.done = done;
+                    }
+                    if due_date.is_some() {
+                        todo.due_date = due_date;
+                    }
+                    if priority.is_some() {
+                        todo.priority = priority;
+                        todo.priority_auto = false;
+                    }
+                    if let Some(tags) = tags {
+                        todo.tags = tags;
+                    }
+                    Ok(())
+                })?;
+                return Ok((todo, false));
+            }
+        }
+        let due_date = match due_date {
+            Some(due_date) => Some(due_date),
+            None => self.default_due_date(list)?,
+        };
+        let todo = Todo {
+            text,
+            done: done.unwrap_or(false),
+            due_date,
+            priority,
+            tags: tags.unwrap_or_default(),
+            ..Default::default()
+        };
+        let todo = match id {
+            Some(id) => {
+                let mut todos = self.get_todos(list)?;
+                let now = Utc::now().to_rfc3339();
+                let mut todo = Todo {
+                    id,
+                    number: Some(next_number(&todos)),
+                    created_at: Some(now.clone()),
+                    updated_at: Some(now.clone()),
+                    completed_at: todo.done.then_some(now),
+                    ..todo
+                };
+                todo.sync_status();
+                todos.push(todo.clone());
+                self.save_todos(list, todos)?;
+                todo
+            }
+            None => self.add_todo(list, todo, None)?,
+        };
+        Ok((todo, true))
+    }
+
+    /// Open todos, oldest first. Todos without a creation time come last.
+    fn oldest_open(&self, list: &str, limit: Option<usize>) -> Result<Vec<Todo>, ToolError> {
+        let mut todos: Vec<Todo> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done)
+            .collect();
+        sort_todos(&mut todos, "created_at", false, None)?;
+        todos.truncate(limit.unwrap_or(usize::MAX));
+        Ok(todos)
+    }
+
+    fn check_due_date(&self, due: DateTime<FixedOffset>, offset: FixedOffset) -> DueDateCheck {
+        let now = Utc::now();
+        let today = now.with_timezone(&offset).date_naive();
+        let day = due.with_timezone(&offset).date_naive();
+        let status = match day.cmp(&today) {
+            Ordering::Less => "past",
+            Ordering::Equal => "today",
+            Ordering::Greater => "future",
+        };
+        DueDateCheck {
+            due_date: due.to_rfc3339(),
+            status,
+            relative: describe_distance(due.with_timezone(&Utc) - now),
+        }
+    }
+
+    /// A random open todo matching `query`, or `None` when nothing matches. The
+    /// same `seed` always picks the same todo from the same list.
+    fn random_todo(
+        &self,
+        list: &str,
+        query: &TodoQuery,
+        seed: Option<u64>,
+    ) -> Result<Option<Todo>, ToolError> {
+        let todos = self.query_todos(list, query)?;
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Ok(todos.choose(&mut rng).cloned())
+    }
+
+    /// Writes the current contents of `store.json` to a new timestamped file in
+    /// the backup directory, then prunes all but the newest `backup_keep`
+    /// backups. Holding the write lock and copying the reloaded entries rather
+    /// than the raw file means a backup never captures a half-finished write.
+    fn backup_store(&self) -> Result<PathBuf, ToolError> {
+        let _guard = self.lock_store();
+        let path = store_file_path(&self.app)?;
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        if path.exists() {
+            reload_store(&store)?;
+        }
+        let entries: serde_json::Map<String, Value> = store.entries().into_iter().collect();
+        let json = serde_json::to_vec_pretty(&entries)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+
+        let dir = path.with_file_name(BACKUP_DIR);
+        std::fs::create_dir_all(&dir).map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let name = format!("store-{}.json", Utc::now().format("%Y%m%dT%H%M%S%.3fZ"));
+        let backup = dir.join(&name);
+        let partial = dir.join(format!("{}.partial", name));
+        std::fs::write(&partial, json)
+            .and_then(|_| std::fs::rename(&partial, &backup))
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+
+        let mut backups: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("store-") && name.ends_with(".json"))
+            })
+            .collect();
+        // The timestamps sort lexicographically, oldest first.
+        backups.sort();
+        let excess = backups.len().saturating_sub(self.backup_keep);
+        for old in &backups[..excess] {
+            if let Err(e) = std::fs::remove_file(old) {
+                eprintln!(
+                    "tauri-todo-mcp: could not prune backup {}: {}",
+                    old.display(),
+                    e
+                );
+            }
+        }
+        Ok(backup)
+    }
+
+    /// Moves subtask `index` of todo `id` out into a new todo right after it,
+    /// with the parent's tags and priority. Parent and new todo are saved
+    /// together.
+    fn promote_subtask(&self, list: &str, id: u64, index: usize) -> Result<Todo, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let position = todos
+            .iter()
+            .position(|todo| todo.id == id)
+            .ok_or_else(|| ToolError::InvalidParameters(format!("todo {} not found", id)))?;
+        let now = Utc::now();
+        let new_id = next_id(&todos, now)?;
+        let number = next_number(&todos);
+        let parent = &mut todos[position];
+        if index >= parent.subtasks.len() {
+            return Err(ToolError::InvalidParameters("index".to_string()));
+        }
+        let before = parent.revision();
+        let subtask = parent.subtasks.remove(index);
+        self.sync_parent_done(parent);
+        parent.record_revision(before);
+        parent.touch();
+        let todo = Todo {
+            id: new_id,
+            number: Some(number),
+            text: subtask.text,
+            done: subtask.done,
+            priority: parent.priority,
+            tags: parent.tags.clone(),
+            created_at: Some(now.to_rfc3339()),
+            updated_at: Some(now.to_rfc3339()),
+            completed_at: subtask.done.then(|| now.to_rfc3339()),
+            ..Default::default()
+        };
+        todos.insert(position + 1, todo.clone());
+        self.save_todos(list, todos)?;
+        Ok(todo)
+    }
+
+    /// Up to `count` other todos sharing at least one tag with todo `id`, most
+    /// shared tags first and in list order among equals.
+    fn related_todos(&self, list: &str, id: u64, count: usize) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let tags: HashSet<String> = find_todo(&mut todos, id)?.tags.iter().cloned().collect();
+        let mut related: Vec<(usize, Todo)> = todos
+            .into_iter()
+            .filter(|todo| todo.id != id)
+            .map(|todo| {
+                let shared = todo.tags.iter().filter(|tag| tags.contains(*tag)).count();
+                (shared, todo)
+            })
+            .filter(|(shared, _)| *shared > 0)
+            .collect();
+        related.sort_by_key(|(shared, _)| std::cmp::Reverse(*shared));
+        Ok(related
+            .into_iter()
+            .take(count)
+            .map(|(_, todo)| todo)
+            .collect())
+    }
+
+    /// Open todos due this week, in `offset`'s calendar with weeks starting on
+    /// `week_start`. Todos due before the week began go to `overdue`; each
+    /// bucket is sorted by due date.
+    fn week_view(
+        &self,
+        list: &str,
+        week_start: Weekday,
+        offset: FixedOffset,
+    ) -> Result<WeekView, ToolError> {
+        let today = Utc::now().with_timezone(&offset).date_naive();
+        let start = today.week(week_start).first_day();
+        let mut overdue = vec![];
+        let mut days: Vec<Vec<Todo>> = vec![vec![]; 7];
+        for todo in self.get_todos(list)? {
+            if todo.done {
+                continue;
+            }
+            let Some(due) = todo.due_date.as_deref().and_then(parse_date) else {
+                continue;
+            };
+            let day = due.with_timezone(&offset).date_naive();
+            if day < start {
+                overdue.push(todo);
+            } else if let Some(bucket) = days.get_mut((day - start).num_days() as usize) {
+                bucket.push(todo);
+            }
+        }
+        let mut buckets = vec![("overdue", overdue)];
+        buckets.extend(
+            start
+                .iter_days()
+                .map(|day| weekday_name(day.weekday()))
+                .zip(days),
+        );
+        for (_, todos) in &mut buckets {
+            sort_todos(todos, "due_date", false, None)?;
+        }
+        Ok(WeekView(buckets))
+    }
+
+    /// Sets `priority` on every todo matching `query`, in a single save. The
+    /// returned todos are the matches, updated unless `dry_run`.
+    fn prioritize_matching(
+        &self,
+        list: &str,
+        query: &TodoQuery,
+        priority: Priority,
+        dry_run: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let mut matched = Vec::new();
+        for todo in todos.iter_mut().filter(|todo| query.matches(todo)) {
+            if !dry_run {
+                let before = todo.revision();
+                todo.priority = Some(priority);
+                todo.priority_auto = false;
+                todo.record_revision(before);
+                todo.touch();
+            }
+            matched.push(todo.clone());
+        }
+        if !dry_run && !matched.is_empty() {
+            self.save_todos(list, todos)?;
+        }
+        Ok(matched)
+    }
+
+    /// `arguments` with a `#42`-style or string `id`, and such ids in
+    /// `depends_on` and `ids`, replaced by numeric ids, so every tool takes ids
+    /// in either `TODO_ID_FORMAT`. References are looked up in the list the
+    /// tool reads, which for `move_todos` is `from`.
+    fn resolve_reference(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+    ) -> Result<Option<Value>, ToolError> {
+        let has_strings = arguments["id"].is_string()
+            || ID_ARRAYS.iter().any(|key| {
+                arguments[key]
+                    .as_array()
+                    .is_some_and(|ids| ids.iter().any(Value::is_string))
+            });
+        if !has_strings {
+            return Ok(None);
+        }
+        // Loaded on the first `#42` reference only.
+        let mut todos: Option<Vec<Todo>> = None;
+        let mut resolve = |field: &str, id: &mut Value| -> Result<(), ToolError> {
+            let Value::String(reference) = id else {
+                return Ok(());
+            };
+            let resolved = match reference.parse::<u64>() {
+                Ok(resolved) => resolved,
+                Err(_) => {
+                    let number = parse_reference(reference)
+                        .ok_or_else(|| ToolError::InvalidParameters(field.to_string()))?;
+                    if todos.is_none() {
+                        let list = if tool_name == "move_todos" {
+                            parse_args::<MoveFromArgs>(arguments)?.from
+                        } else {
+                            self.list_name(arguments)?
+                        };
+                        todos = Some(self.get_todos(&list)?);
+                    }
+                    todos
+                        .iter()
+                        .flatten()
+                        .find(|todo| todo.number == Some(number))
+                        .map(|todo| todo.id)
+                        .ok_or_else(|| {
+                            ToolError::InvalidParameters(format!("todo #{} not found", number))
+                        })?
+                }
+            };
+            *id = Value::from(resolved);
+            Ok(())
+        };
+        let mut resolved = arguments.clone();
+        if let Some(id) = resolved.get_mut("id") {
+            resolve("id", id)?;
+        }
+        for key in ID_ARRAYS {
+            let ids = resolved.get_mut(key).and_then(Value::as_array_mut);
+            for id in ids.into_iter().flatten() {
+                resolve(key, id)?;
+            }
+        }
+        Ok(Some(resolved))
+    }
+
+    fn set_assignee(
+        &self,
+        list: &str,
+        id: u64,
+        assignee: Option<String>,
+    ) -> Result<Todo, ToolError> {
+        self.modify_todo(list, id, |todo| {
+            todo.assignee = assignee;
+            Ok(())
+        })
+    }
+
+    /// `workload` per assignee, busiest first. Assignees without open todos
+    /// are left out.
+    fn workload_by_assignee(&self, list: &str) -> Result<Vec<AssigneeWorkload>, ToolError> {
+        let mut workloads: BTreeMap<Option<String>, Workload> = BTreeMap::new();
+        for todo in self.get_todos(list)?.iter().filter(|todo| !todo.done) {
+            workloads
+                .entry(todo.assignee.clone())
+                .or_default()
+                .add(todo);
+        }
+        let mut workloads: Vec<AssigneeWorkload> = workloads
+            .into_iter()
+            .map(|(assignee, workload)| AssigneeWorkload { assignee, workload })
+            .collect();
+        workloads.sort_by_key(|workload| std::cmp::Reverse(workload.workload.open_todos));
+        Ok(workloads)
+    }
+
+    fn archived_lists(&self) -> Result<Vec<String>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let mut lists: Vec<String> = store
+            .keys()
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix(ARCHIVE_KEY_PREFIX)
+                    .map(|name| name.to_string())
+            })
+            .collect();
+        lists.sort();
+        Ok(lists)
+    }
+
+    /// Moves every todo of `name` into the archive, along with its settings,
+    /// focus, tombstones and snapshots, and drops the list. Returns how many
+    /// todos were archived.
+    fn archive_list(&self, name: &str) -> Result<usize, ToolError> {
+        if name == DEFAULT_LIST || name == self.default_list {
+            return Err(ToolError::InvalidParameters(
+                "the default list cannot be archived".to_string(),
+            ));
+        }
+        if !self.get_lists()?.iter().any(|list| list == name) {
+            return Err(ToolError::InvalidParameters(format!(
+                "list {} not found",
+                name
+            )));
+        }
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let key = format!("{}{}", ARCHIVE_KEY_PREFIX, name);
+        if store.has(&key) {
+            return Err(ToolError::InvalidParameters(format!(
+                "an archived list named {} already exists",
+                name
+            )));
+        }
+        let todos = self.get_todos(name)?;
+        let count = todos.len();
+        let mut data = BTreeMap::new();
+        for prefix in LIST_DATA_KEY_PREFIXES {
+            let data_key = format!("{}{}", prefix, name);
+            if let Some(value) = store.get(&data_key) {
+                data.insert(prefix.to_string(), value);
+                store.delete(&data_key);
+            }
+        }
+        let archive = ArchivedList {
+            archived_at: Utc::now().to_rfc3339(),
+            todos,
+            data,
+        };
+        store.set(
+            key,
+            serde_json::to_value(&archive).map_err(|e| ToolError::ExecutionError(e.to_string()))?,
+        );
+        save_store(&store)?;
+        self.storage.remove(name)?;
+        Ok(count)
+    }
+
+    /// Brings an archived list back under its old name. Returns how many todos
+    /// were restored.
+    fn restore_list(&self, name: &str) -> Result<usize, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let key = format!("{}{}", ARCHIVE_KEY_PREFIX, name);
+        let archive = store
+            .get(&key)
+            .and_then(|value| serde_json::from_value::<ArchivedList>(value).ok())
+            .ok_or_else(|| {
+                ToolError::InvalidParameters(format!("archived list {} not found", name))
+            })?;
+        if self.storage.has_list(name)? {
+            return Err(ToolError::InvalidParameters(format!(
+                "list {} already exists",
+                name
+            )));
+        }
+        let count = archive.todos.len();
+        self.save_or_create(name, archive.todos)?;
+        for prefix in LIST_DATA_KEY_PREFIXES {
+            let data_key = format!("{}{}", prefix, name);
+            match archive.data.get(*prefix) {
+                Some(value) => store.set(data_key, value.clone()),
+                None => {
+                    store.delete(&data_key);
+                }
+            }
+        }
+        store.delete(&key);
+        save_store(&store)?;
+        Ok(count)
+    }
+
+    /// The open todo whose due date passed longest ago, if any is overdue.
+    fn most_overdue(&self, list: &str) -> Result<Option<Todo>, ToolError> {
+        let now = Utc::now();
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| !todo.done && is_overdue(todo, now))
+            .min_by_key(|todo| todo.due_date.as_deref().and_then(parse_date)))
+    }
+
+    /// Moves the todos with `ids` from `from` to the end of `to`, writing
+    /// each list once. `to` must exist unless `create` is set, and must not
+    /// hold any of the moved ids. Moved todos get new `#` numbers in `to`. The
+    /// source is saved first and put back if the destination can't be saved,
+    /// so a failure leaves both lists as they were. Sync clients of `from` see
+    /// the moved todos as deleted.
+    fn move_todos(
+        &self,
+        ids: &[u64],
+        from: &str,
+        to: &str,
+        create: bool,
+    ) -> Result<MoveResult, ToolError> {
+        if from == to {
+            return Err(ToolError::InvalidParameters(
+                "from and to must be different lists".to_string(),
+            ));
+        }
+        if !create && !self.get_lists()?.iter().any(|name| name == to) {
+            return Err(ToolError::InvalidParameters(format!(
+                "list {} not found",
+                to
+            )));
+        }
+        let source = self.get_todos(from)?;
+        let (moving, staying): (Vec<Todo>, Vec<Todo>) = source
+            .iter()
+            .cloned()
+            .partition(|todo| ids.contains(&todo.id));
+        let moved: Vec<u64> = moving.iter().map(|todo| todo.id).collect();
+        let not_found = ids
+            .iter()
+            .filter(|id| !moved.contains(id))
+            .copied()
+            .collect();
+        if moving.is_empty() {
+            return Ok(MoveResult { moved, not_found });
+        }
+        let mut todos = self.get_todos(to)?;
+        if let Some(todo) = todos.iter().find(|todo| moved.contains(&todo.id)) {
+            return Err(ToolError::InvalidParameters(format!(
+                "list {} already has a todo with id {}",
+                to, todo.id
+            )));
+        }
+        let now = Utc::now().to_rfc3339();
+        todos.extend(moving.into_iter().map(|todo| Todo {
+            number: None,
+            updated_at: Some(now.clone()),
+            ..todo
+        }));
+        self.save_todos(from, staying)?;
+        if let Err(e) = self.save_or_create(to, todos) {
+            self.save_todos(from, source)?;
+            return Err(e);
+        }
+        self.record_deletions(from, &moved)?;
+        Ok(MoveResult { moved, not_found })
+    }
+
+    /// Days, in `offset`'s calendar, on which more than `max_per_day` open
+    /// todos are due, earliest first. Each day's todos are sorted by due date.
+    fn overloaded_days(
+        &self,
+        list: &str,
+        max_per_day: usize,
+        offset: FixedOffset,
+    ) -> Result<Vec<OverloadedDay>, ToolError> {
+        let mut days: BTreeMap<NaiveDate, Vec<Todo>> = BTreeMap::new();
+        for todo in self.get_todos(list)? {
+            if todo.done {
+                continue;
+            }
+            if let Some(due) = todo.due_date.as_deref().and_then(parse_date) {
+                let day = due.with_timezone(&offset).date_naive();
+                days.entry(day).or_default().push(todo);
+            }
+        }
+        days.into_iter()
+            .filter(|(_, todos)| todos.len() > max_per_day)
+            .map(|(date, mut todos)| {
+                sort_todos(&mut todos, "due_date", false, None)?;
+                Ok(OverloadedDay {
+                    date,
+                    count: todos.len(),
+                    todos,
+                })
+            })
+            .collect()
+    }
+
+    /// Marks every todo completed within the last `within_hours` as open
+    /// again, in a single save. The returned todos are the matches, reopened
+    /// unless `dry_run`.
+    fn reopen_recent(
+        &self,
+        list: &str,
+        within_hours: u32,
+        dry_run: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let since = Utc::now()
+            .checked_sub_signed(chrono::Duration::hours(within_hours.into()))
+            .ok_or_else(|| ToolError::InvalidParameters("within_hours".to_string()))?;
+        let mut todos = self.get_todos(list)?;
+        let mut reopened = Vec::new();
+        for todo in todos.iter_mut().filter(|todo| {
+            todo.done
+                && todo
+                    .completed_at
+                    .as_deref()
+                    .and_then(parse_date)
+                    .is_some_and(|completed| completed >= since)
+        }) {
+            if !dry_run {
+                let before = todo.revision();
+                todo.done = false;
+                todo.record_revision(before);
+                todo.touch();
+            }
+            reopened.push(todo.clone());
+        }
+        if !dry_run && !reopened.is_empty() {
+            self.save_todos(list, todos)?;
+        }
+        Ok(reopened)
+    }
+
+    fn schema_version(&self) -> Result<u64, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        Ok(store
+            .get(SCHEMA_VERSION_KEY)
+            .and_then(|version| version.as_u64())
+            .unwrap_or(0))
+    }
+
+    /// Brings the stored data up to `SCHEMA_VERSION`, recording the version in
+    /// `store.json`. Before version 2, every list is rewritten so its todos
+    /// carry a `status` derived from `done`. Data written by a newer build is
+    /// left alone.
+    fn migrate_store(&self) -> Result<(), ToolError> {
+        let _guard = self.lock_store();
+        let version = self.schema_version()?;
+        if version > SCHEMA_VERSION {
+            eprintln!(
+                "tauri-todo-mcp: {} has schema version {}, newer than {}; leaving it as is",
+                STORE_PATH, version, SCHEMA_VERSION
+            );
+        } else if version < SCHEMA_VERSION {
+            if version < 2 {
+                for list in self.get_lists()? {
+                    if self.storage.has_list(&list)? {
+                        self.save_todos(&list, self.get_todos(&list)?)?;
+                    }
+                }
+            }
+            let store = self
+                .app
+                .store(STORE_PATH)
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            store.set(SCHEMA_VERSION_KEY, SCHEMA_VERSION);
+            save_store(&store)?;
+            let _ = self.migrated_from.set(version);
+        }
+        Ok(())
+    }
+
+    fn schema_status(&self) -> Result<SchemaStatus, ToolError> {
+        let schema_version = self.schema_version()?;
+        Ok(SchemaStatus {
+            schema_version,
+            latest_version: SCHEMA_VERSION,
+            pending: schema_version < SCHEMA_VERSION,
+            migrated_from: self.migrated_from.get().copied(),
+        })
+    }
+
+    fn export_snapshot(&self, list: &str) -> Result<SnapshotBundle, ToolError> {
+        Ok(SnapshotBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            server: mcp_server::Router::name(self),
+            server_version: env!("CARGO_PKG_VERSION").to_string(),
+            generated_at: Utc::now().to_rfc3339(),
+            list: list.to_string(),
+            todos: self.get_todos(list)?,
+        })
+    }
+
+    /// Appends the todos of an `export_snapshot` bundle to `list` under new
+    /// ids and numbers, keeping their dependencies on each other.
+    fn import_todos(&self, list: &str, bundle: &Value) -> Result<Vec<Todo>, ToolError> {
+        if bundle["format"] != BUNDLE_FORMAT {
+            return Err(ToolError::InvalidParameters(
+                "bundle: not a bundle from export_snapshot".to_string(),
+            ));
+        }
+        self.check_batch("bundle", bundle["todos"].as_array().map_or(0, Vec::len))?;
+        let bundle: SnapshotBundle = serde_json::from_value(bundle.clone())
+            .map_err(|e| ToolError::InvalidParameters(format!("bundle: {}", e)))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(ToolError::InvalidParameters(format!(
+                "bundle: version {} is newer than the supported version {}",
+                bundle.version, BUNDLE_VERSION
+            )));
+        }
+        let mut todos = self.get_todos(list)?;
+        let now = Utc::now();
+        let first_id = next_ids(&todos, now, bundle.todos.len())?;
+        let first_number = next_number(&todos);
+        let ids: HashMap<u64, u64> = bundle
+            .todos
+            .iter()
+            .enumerate()
+            .map(|(offset, todo)| (todo.id, first_id + offset as u64))
+            .collect();
+        let imported: Vec<Todo> = bundle
+            .todos
+            .into_iter()
+            .enumerate()
+            .map(|(offset, todo)| Todo {
+                id: first_id + offset as u64,
+                number: Some(first_number + offset as u64),
+                depends_on: todo
+                    .depends_on
+                    .iter()
+                    .filter_map(|id| ids.get(id).copied())
+                    .collect(),
+                updated_at: Some(now.to_rfc3339()),
+                ..todo
+            })
+            .collect();
+        todos.extend(imported.iter().cloned());
+        self.save_todos(list, todos)?;
+        Ok(imported)
+    }
+
+    /// Todos whose id, written in decimal, starts with `prefix`. With
+    /// `unique`, more than one match is an error naming the candidates.
+    fn find_by_id_prefix(
+        &self,
+        list: &str,
+        prefix: &str,
+        unique: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let matches: Vec<Todo> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| todo.id.to_string().starts_with(prefix))
+            .collect();
+        if unique && matches.len() > 1 {
+            let ids: Vec<String> = matches.iter().map(|todo| todo.id.to_string()).collect();
+            return Err(ToolError::InvalidParameters(format!(
+                "prefix {} is ambiguous: {}",
+                prefix,
+                ids.join(", ")
+            )));
+        }
+        Ok(matches)
+    }
+
+    /// Projects when the open todos would all be done if completions kept
+    /// the average daily pace of the last `window_days`, counting days in
+    /// `offset`.
+    fn forecast(
+        &self,
+        list: &str,
+        window_days: u32,
+        offset: FixedOffset,
+    ) -> Result<Forecast, ToolError> {
+        let todos = self.get_todos(list)?;
+        let now = Utc::now();
+        let since = now
+            .checked_sub_signed(chrono::Duration::days(window_days.into()))
+            .ok_or_else(|| ToolError::InvalidParameters("window_days".to_string()))?;
+        let open_todos = todos.iter().filter(|todo| !todo.done).count();
+        let completed_in_window = todos
+            .iter()
+            .filter(|todo| {
+                todo.done
+                    && todo
+                        .completed_at
+                        .as_deref()
+                        .and_then(parse_date)
+                        .is_some_and(|completed| completed >= since)
+            })
+            .count();
+        let per_day = completed_in_window as f64 / f64::from(window_days);
+        let assumption = format!(
+            "assumes the pace of the last {} days ({} completed) continues and no todos are added",
+            window_days, completed_in_window
+        );
+        let (estimated_days, explanation) = if open_todos == 0 {
+            (Some(0), "there are no open todos".to_string())
+        } else if completed_in_window == 0 {
+            (
+                None,
+                format!(
+                    "no todos were completed in the last {} days, \
+                     so there is no pace to project from",
+                    window_days
+                ),
+            )
+        } else {
+            (
+                Some((open_todos as f64 / per_day).ceil() as u64),
+                assumption,
+            )
+        };
+        let today = now.with_timezone(&offset).date_naive();
+        Ok(Forecast {
+            open_todos,
+            window_days,
+            completed_in_window,
+            per_day,
+            estimated_days,
+            estimated_date: estimated_days
+                .and_then(|days| today.checked_add_days(chrono::Days::new(days))),
+            explanation,
+        })
+    }
+
+    /// Moves one todo from `list` to `to`, which must already exist unless
+    /// `create`. Returns the todo as stored in `to`.
+    fn set_list(&self, list: &str, id: u64, to: &str, create: bool) -> Result<Todo, ToolError> {
+        if self.move_todos(&[id], list, to, create)?.moved.is_empty() {
+            return Err(ToolError::InvalidParameters(format!(
+                "todo {} not found",
+                id
+            )));
+        }
+        self.get_todos(to)?
+            .into_iter()
+            .find(|todo| todo.id == id)
+            .ok_or_else(|| ToolError::InvalidParameters(format!("todo {} not found", id)))
+    }
+
+    /// Adds `tags` to every todo matching `query` that lacks one of them, in
+    /// a single save. The returned todos are the ones that gain a tag,
+    /// updated unless `dry_run`.
+    fn tag_matching(
+        &self,
+        list: &str,
+        query: &TodoQuery,
+        tags: &[String],
+        dry_run: bool,
+    ) -> Result<Vec<Todo>, ToolError> {
+        let mut todos = self.get_todos(list)?;
+        let mut tagged = Vec::new();
+        for todo in todos
+            .iter_mut()
+            .filter(|todo| query.matches(todo) && tags.iter().any(|tag| !todo.tags.contains(tag)))
+        {
+            if !dry_run {
+                for tag in tags {
+                    if !todo.tags.contains(tag) {
+                        todo.tags.push(tag.clone());
+                    }
+                }
+                todo.touch();
+            }
+            tagged.push(todo.clone());
+        }
+        if !dry_run && !tagged.is_empty() {
+            self.save_todos(list, todos)?;
+        }
+        Ok(tagged)
+    }
+
+    /// Todos of the snapshot `name`, or the current todos for `current`.
+    fn snapshot_todos(&self, list: &str, name: &str) -> Result<Vec<Todo>, ToolError> {
+        if name == CURRENT_SNAPSHOT {
+            return self.get_todos(list);
+        }
+        self.get_snapshots(list)?
+            .into_iter()
+            .find(|snapshot| snapshot.name == name)
+            .map(|snapshot| snapshot.todos)
+            .ok_or_else(|| ToolError::InvalidParameters(format!("snapshot {} not found", name)))
+    }
+
+    /// What changed going from snapshot `from` to snapshot `to`, matching
+    /// todos by id. Either side may be `current`.
+    fn diff_snapshots(&self, list: &str, from: &str, to: &str) -> Result<SnapshotDiff, ToolError> {
+        let before = self.snapshot_todos(list, from)?;
+        let after = self.snapshot_todos(list, to)?;
+        let mut changed = vec![];
+        let mut added = vec![];
+        for todo in &after {
+            match before.iter().find(|old| old.id == todo.id) {
+                Some(old) => {
+                    let fields = changed_fields(old, todo)?;
+                    if !fields.is_empty() {
+                        changed.push(TodoChange {
+                            id: todo.id,
+                            fields,
+                        });
+                    }
+                }
+                None => added.push(todo.clone()),
+            }
+        }
+        let removed = before
+            .into_iter()
+            .filter(|old| !after.iter().any(|todo| todo.id == old.id))
+            .collect();
+        Ok(SnapshotDiff {
+            from: from.to_string(),
+            to: to.to_string(),
+            added,
+            removed,
+            changed,
+        })
+    }
+
+    /// Result of a mutating tool that otherwise returns nothing: `{todos}`
+    /// with the updated list when `return_list`, else empty text.
+    fn list_result(&self, list: &str, return_list: bool) -> Result<Vec<Content>, ToolError> {
+        if !return_list {
+            return Ok(vec![Content::text("".to_string())]);
+        }
+        let todos = self.get_todos(list)?;
+        Ok(vec![Content::text(
+            self.to_json(&serde_json::json!({ "todos": todos }))?,
+        )])
+    }
+
+    /// The `count` pairs of tags found together on the most todos, most
+    /// frequent first. Pairs are alphabetical within and break ties.
+    fn tag_cooccurrence(&self, list: &str, count: usize) -> Result<Vec<TagPair>, ToolError> {
+        let mut pairs: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for todo in self.get_todos(list)? {
+            let tags: BTreeSet<String> = todo.tags.into_iter().collect();
+            for (index, first) in tags.iter().enumerate() {
+                for second in tags.iter().skip(index + 1) {
+                    *pairs.entry((first.clone(), second.clone())).or_default() += 1;
+                }
+            }
+        }
+        let mut pairs: Vec<TagPair> = pairs
+            .into_iter()
+            .map(|((first, second), count)| TagPair {
+                tags: [first, second],
+                count,
+            })
+            .collect();
+        pairs.sort_by_key(|pair| std::cmp::Reverse(pair.count));
+        pairs.truncate(count);
+        Ok(pairs)
+    }
+
+    /// Open todos with status `in_progress` or tagged `tag`, compared with a
+    /// work-in-progress `limit`.
+    fn wip_status(&self, list: &str, tag: String, limit: usize) -> Result<WipStatus, ToolError> {
+        let todos: Vec<Todo> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| {
+                !todo.done && (todo.status == Status::InProgress || todo.tags.contains(&tag))
+            })
+            .collect();
+        Ok(WipStatus {
+            tag,
+            in_progress: todos.len(),
+            limit,
+            over_limit: todos.len() > limit,
+            todos,
+        })
+    }
+
+    /// Sets `status`, and `done` to match it. Starting a todo fails when
+    /// `TODO_WIP_LIMIT` other open todos in the list are already
+    /// `in_progress`.
+    fn set_status(&self, list: &str, id: u64, status: Status) -> Result<Todo, ToolError> {
+        if let (Status::InProgress, Some(limit)) = (status, self.wip_limit) {
+            let in_progress = self
+                .get_todos(list)?
+                .iter()
+                .filter(|todo| todo.id != id && !todo.done && todo.status == Status::InProgress)
+                .count();
+            if in_progress >= limit {
+                return Err(ToolError::InvalidParameters(format!(
+                    "cannot start todo {}: {} todos are already in_progress and {} is {}; \
+                     finish or pause one first",
+                    id, in_progress, WIP_LIMIT_ENV, limit
+                )));
+            }
+        }
+        self.modify_todo(list, id, |todo| {
+            todo.status = status;
+            todo.done = status == Status::Done;
+            Ok(())
+        })
+    }
+
+    /// Appends the todos of an outline (see `parse_outline`) to `list` in a
+    /// single save. Returns how many todos and subtasks were created.
+    fn import_outline(&self, list: &str, text: &str) -> Result<(usize, usize), ToolError> {
+        let outline = parse_outline(text)?;
+        self.check_batch("text", outline.len())?;
+        if outline.is_empty() {
+            return Err(ToolError::InvalidParameters(
+                "text: the outline has no items".to_string(),
+            ));
+        }
+        if let Some(todo) = outline
+            .iter()
+            .find(|todo| todo.subtasks.len() > self.max_subtasks)
+        {
+            return Err(ToolError::InvalidParameters(format!(
+                "todo {:?} has more than the maximum of {} subtasks",
+                todo.text, self.max_subtasks
+            )));
+        }
+        let mut todos = self.get_todos(list)?;
+        let now = Utc::now();
+        let first_id = next_ids(&todos, now, outline.len())?;
+        let first_number = next_number(&todos);
+        let due_date = self.default_due_date(list)?;
+        let count = outline.len();
+        let subtasks = outline.iter().map(|todo| todo.subtasks.len()).sum();
+        todos.extend(outline.into_iter().enumerate().map(|(offset, todo)| Todo {
+            id: first_id + offset as u64,
+            number: Some(first_number + offset as u64),
+            due_date: due_date.clone(),
+            created_at: Some(now.to_rfc3339()),
+            updated_at: Some(now.to_rfc3339()),
+            completed_at: todo.done.then(|| now.to_rfc3339()),
+            ..todo
+        }));
+        self.save_todos(list, todos)?;
+        Ok((count, subtasks))
+    }
+
+    /// Links found in each todo's text and notes, by todo id. Todos without
+    /// links are left out.
+    fn extract_links(&self, list: &str) -> Result<BTreeMap<u64, Vec<String>>, ToolError> {
+        Ok(self
+            .get_todos(list)?
+            .into_iter()
+            .filter_map(|todo| {
+                let mut links = find_links(&todo.text);
+                for link in find_links(todo.notes.as_deref().unwrap_or_default()) {
+                    if !links.contains(&link) {
+                        links.push(link);
+                    }
+                }
+                (!links.is_empty()).then_some((todo.id, links))
+            })
+            .collect())
+    }
+
+    /// Todos completed since the start of today in `offset`'s calendar, in
+    /// the order they were completed.
+    fn done_today(&self, list: &str, offset: FixedOffset) -> Result<Vec<Todo>, ToolError> {
+        let today = Utc::now().with_timezone(&offset).date_naive();
+        let mut todos: Vec<(DateTime<FixedOffset>, Todo)> = self
+            .get_todos(list)?
+            .into_iter()
+            .filter(|todo| todo.done)
+            .filter_map(|todo| {
+                let completed = todo.completed_at.as_deref().and_then(parse_date)?;
+                (completed.with_timezone(&offset).date_naive() == today)
+                    .then_some((completed, todo))
+            })
+            .collect();
+        todos.sort_by_key(|(completed, _)| *completed);
+        Ok(todos.into_iter().map(|(_, todo)| todo).collect())
+    }
+
+    /// Rejects a batch of `len` items passed as `name` when it exceeds
+    /// `TODO_MAX_BATCH`, before any of it is processed.
+    fn check_batch(&self, name: &str, len: usize) -> Result<(), ToolError> {
+        if len <= self.max_batch {
+            return Ok(());
+        }
+        Err(ToolError::InvalidParameters(format!(
+            "{}: {} items exceed the limit of {} per call; split the batch or raise {}",
+            name, len, self.max_batch, MAX_BATCH_ENV
+        )))
+    }
+
+    /// Sets or, when `every` is `None`, clears the recurring reminder of
+    /// todo `id`. The first occurrence is at `starts_at`, or one interval
+    /// from now.
+    fn set_reminder_schedule(
+        &self,
+        list: &str,
+        id: u64,
+        every: Option<String>,
+        starts_at: Option<String>,
+    ) -> Result<Todo, ToolError> {
+        let schedule = match every {
+            None => None,
+            Some(every) => {
+                let interval = parse_due_offset(&every)
+                    .ok_or_else(|| ToolError::InvalidParameters("every".to_string()))?;
+                let starts_at = match starts_at {
+                    None => Utc::now()
+                        .checked_add_signed(interval)
+                        .ok_or_else(|| ToolError::InvalidParameters("every".to_string()))?
+                        .to_rfc3339(),
+                    Some(starts_at) => parse_date(&starts_at)
+                        .ok_or_else(|| ToolError::InvalidParameters("starts_at".to_string()))?
+                        .to_rfc3339(),
+                };
+                Some(ReminderSchedule {
+                    every: every.trim().to_string(),
+                    starts_at,
+                })
+            }
+        };
+        self.modify_todo(list, id, |todo| {
+            todo.reminder_schedule = schedule;
+            Ok(())
+        })
+    }
+
+    fn dispatch(&self, tool_name: &str, arguments: &Value) -> Result<Vec<Content>, ToolError> {
+        for (name, value) in arguments.as_object().into_iter().flatten() {
+            if let Value::Array(values) = value {
+                self.check_batch(name, values.len())?;
+            }
+        }
+        let resolved = self.resolve_reference(tool_name, arguments)?;
+        let arguments = resolved.as_ref().unwrap_or(arguments);
+        if MUTATING_TOOLS.contains(&tool_name) {
+            self.require_list(&self.list_name(arguments)?)?;
+        }
+        match tool_name {
+            "get_todos" => {
+                let list = self.list_name(arguments)?;
+                let todos = self.get_todos(&list)?;
+                self.todos_response(todos, arguments)
+            }
+            "add_todo" => {
+                let list = self.list_name(arguments)?;
+                let AddArgs {
+                    text,
+                    done,
+                    due_date,
+                    priority,
+                    tags,
+                    position,
+                    return_list,
+                } = parse_args(arguments)?;
+                let due_date = match due_date {
+                    Some(due) => Some(due.to_rfc3339()),
+                    None => self.default_due_date(&list)?,
+                };
+                let todo = Todo {
+                    text,
+                    done,
+                    due_date,
+                    priority,
+                    tags,
+                    ..Default::default()
+                };
+                let position =
+                    position.map(|position| usize::try_from(position).unwrap_or(usize::MAX));
+                let todo = self.add_todo(&list, todo, position)?;
+                if return_list {
+                    let todos = self.get_todos(&list)?;
+                    return Ok(vec![Content::text(
+                        self.to_json(&serde_json::json!({ "todo": todo, "todos": todos }))?,
+                    )]);
+                }
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "remove_todo" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let ReturnListArgs { return_list } = parse_args(arguments)?;
+                self.remove_todo(&list, id)?;
+                self.list_result(&list, return_list)
+            }
+            "update_todo" => {
+                let list = self.list_name(arguments)?;
+                let UpdateArgs { id, text, done } = parse_args(arguments)?;
+                let ReturnListArgs { return_list } = parse_args(arguments)?;
+                self.update_todo(&list, id, text, done)?;
+                self.list_result(&list, return_list)
+            }
+            "set_due_date" => {
+                let list = self.list_name(arguments)?;
+                let DueDateArgs { id, due_date } = parse_args(arguments)?;
+                let todo = self.set_due_date(&list, id, due_date.map(|due| due.to_rfc3339()))?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "reschedule_overdue" => {
+                let list = self.list_name(arguments)?;
+                let RescheduleArgs { to } = parse_args(arguments)?;
+                let to = to.unwrap_or_else(end_of_today);
+                let count = self.reschedule_overdue(&list, to)?;
+                Ok(vec![Content::text(
+                    self.to_json(&serde_json::json!({ "rescheduled": count }))?,
+                )])
+            }
+            "set_priority" => {
+                let list = self.list_name(arguments)?;
+                let PriorityArgs { id, priority } = parse_args(arguments)?;
+                let todo = self.set_priority(&list, id, priority)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "agenda" => {
+                let list = self.list_name(arguments)?;
+                let AgendaArgs { date } = parse_args(arguments)?;
+                let date = date.unwrap_or_else(|| Local::now().date_naive());
+                let agenda = self.agenda(&list, date)?;
+                Ok(vec![Content::text(agenda)])
+            }
+            "add_subtask" => {
+                let list = self.list_name(arguments)?;
+                let TextArgs { id, text } = parse_args(arguments)?;
+                let todo = self.add_subtask(&list, id, text)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "set_subtask_done" => {
+                let list = self.list_name(arguments)?;
+                let SubtaskDoneArgs { id, index, done } = parse_args(arguments)?;
+                let todo = self.set_subtask_done(&list, id, index, done)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "toggle_all_subtasks" => {
+                let list = self.list_name(arguments)?;
+                let DoneArgs { id, done } = parse_args(arguments)?;
+                let todo = self.toggle_all_subtasks(&list, id, done)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "open_todos" | "done_todos" => {
+                let list = self.list_name(arguments)?;
+                let done = tool_name == "done_todos";
+                let todos = self
+                    .get_todos(&list)?
+                    .into_iter()
+                    .filter(|todo| todo.done == done)
+                    .collect();
+                self.todos_response(todos, arguments)
+            }
+            "set_estimate" => {
+                let list = self.list_name(arguments)?;
+                let EstimateArgs { id, minutes } = parse_args(arguments)?;
+                let todo = self.set_estimate(&list, id, minutes)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "log_time" => {
+                let list = self.list_name(arguments)?;
+                let LogTimeArgs { id, minutes } = parse_args(arguments)?;
+                let todo = self.log_time(&list, id, minutes)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "workload" => {
+                let list = self.list_name(arguments)?;
+                let workload = self.workload(&list)?;
+                Ok(vec![Content::text(self.to_json(&workload)?)])
+            }
+            "set_notes" => {
+                let list = self.list_name(arguments)?;
+                let NotesArgs { id, notes } = parse_args(arguments)?;
+                let todo = self.set_notes(&list, id, notes)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "export_ics" => {
+                let list = self.list_name(arguments)?;
+                let IcsArgs { include_all } = parse_args(arguments)?;
+                let todos: Vec<Todo> = self
+                    .get_todos(&list)?
+                    .into_iter()
+                    .filter(|todo| include_all || todo.due_date.is_some())
+                    .collect();
+                Ok(vec![Content::text(render_ics(&todos))])
+            }
+            "list_todos_changed_since" => {
+                let list = self.list_name(arguments)?;
+                let SinceArgs { since } = parse_args(arguments)?;
+                let changes = self.changed_since(&list, since)?;
+                Ok(vec![Content::text(self.to_json(&changes)?)])
+            }
+            "set_tags" => {
+                let list = self.list_name(arguments)?;
+                let TagsArgs { id, tags } = parse_args(arguments)?;
+                let todo = self.set_tags(&list, id, tags)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "retag" => {
+                let list = self.list_name(arguments)?;
+                let RetagArgs {
+                    from_tag: from,
+                    to_tag: to,
+                } = parse_args(arguments)?;
+                if from == to {
+                    return Err(ToolError::InvalidParameters(
+                        "from_tag and to_tag must differ".to_string(),
+                    ));
+                }
+                let count = self.retag(&list, &from, Some(&to))?;
+                Ok(vec![Content::text(
+                    self.to_json(&serde_json::json!({ "affected": count }))?,
+                )])
+            }
+            "remove_tag_everywhere" => {
+                let list = self.list_name(arguments)?;
+                let TagArgs { tag } = parse_args(arguments)?;
+                let count = self.retag(&list, &tag, None)?;
+                Ok(vec![Content::text(
+                    self.to_json(&serde_json::json!({ "affected": count }))?,
+                )])
+            }
+            "create_snapshot" => {
+                let list = self.list_name(arguments)?;
+                let NameArgs { name } = parse_args(arguments)?;
+                let snapshot = self.create_snapshot(&list, name)?;
+                Ok(vec![Content::text(
+                    self.to_json(&SnapshotInfo::from(&snapshot))?,
+                )])
+            }
+            "list_snapshots" => {
+                let list = self.list_name(arguments)?;
+                let snapshots: Vec<SnapshotInfo> = self
+                    .get_snapshots(&list)?
+                    .iter()
+                    .map(SnapshotInfo::from)
+                    .collect();
+                Ok(vec![Content::text(self.to_json(&snapshots)?)])
+            }
+            "restore_snapshot" => {
+                let list = self.list_name(arguments)?;
+                let NameArgs { name } = parse_args(arguments)?;
+                let backup = self.restore_snapshot(&list, &name)?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "restored": name, "backup": backup }),
+                )?)])
+            }
+            "revert_todo" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let todo = self.revert_todo(&list, id)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "sort_todos" => {
+                let list = self.list_name(arguments)?;
+                let SortArgs { by, order, locale } = parse_args(arguments)?;
+                let todos = self.sort_todos(&list, &by, order == Order::Desc, locale.as_deref())?;
+                self.todos_response(todos, arguments)
+            }
+            "stale_todos" => {
+                let list = self.list_name(arguments)?;
+                let StaleArgs { older_than_days } = parse_args(arguments)?;
+                let todos = self.stale_todos(&list, older_than_days)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "metrics" => Ok(vec![Content::text(self.to_json(&self.metrics())?)]),
+            "reset_metrics" => {
+                self.reset_metrics();
+                Ok(vec![Content::text("".to_string())])
+            }
+            "split_todo" => {
+                let list = self.list_name(arguments)?;
+                let SplitArgs { id, texts } = parse_args(arguments)?;
+                let todos = self.split_todo(&list, id, texts)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "streak" => {
+                let list = self.list_name(arguments)?;
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let streak = self.streak(&list, offset)?;
+                Ok(vec![Content::text(self.to_json(&streak)?)])
+            }
+            "compact" => {
+                let list = self.list_name(arguments)?;
+                let todos = self.compact_todos(&list)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "add_attachment" | "remove_attachment" => {
+                let list = self.list_name(arguments)?;
+                let AttachmentArgs { id, attachment } = parse_args(arguments)?;
+                let todo = if tool_name == "add_attachment" {
+                    self.add_attachment(&list, id, attachment)?
+                } else {
+                    self.remove_attachment(&list, id, attachment)?
+                };
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "query_todos" => {
+                let list = self.list_name(arguments)?;
+                let QueryArgs { filter } = parse_args(arguments)?;
+                let QuerySortArgs { sort } = parse_args(arguments)?;
+                let mut todos = self.query_todos(&list, &filter.unwrap_or_default())?;
+                if let Some(SortArgs { by, order, locale }) = sort {
+                    sort_todos(&mut todos, &by, order == Order::Desc, locale.as_deref())?;
+                }
+                self.todos_response(todos, arguments)
+            }
+            "add_reminder" | "remove_reminder" => {
+                let list = self.list_name(arguments)?;
+                let ReminderArgs { id, at } = parse_args(arguments)?;
+                let todo = if tool_name == "add_reminder" {
+                    self.add_reminder(&list, id, at)?
+                } else {
+                    self.remove_reminder(&list, id, at)?
+                };
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "due_reminders" => {
+                let list = self.list_name(arguments)?;
+                let todos = self.due_reminders(&list)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "dismiss_reminders" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let todo = self.dismiss_reminders(&list, id)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "create_list" => {
+                let NameArgs { name } = parse_args(arguments)?;
+                let lists = self.create_list(&name)?;
+                Ok(vec![Content::text(self.to_json(&lists)?)])
+            }
+            "normalize_todos" => {
+                let list = self.list_name(arguments)?;
+                let normalization: Normalization = parse_args(arguments)?;
+                let changed = self.normalize_todos(&list, normalization)?;
+                Ok(vec![Content::text(
+                    self.to_json(&serde_json::json!({ "changed": changed }))?,
+                )])
+            }
+            "set_dependencies" => {
+                let list = self.list_name(arguments)?;
+                let DependenciesArgs { id, depends_on } = parse_args(arguments)?;
+                let todo = self.set_dependencies(&list, id, depends_on)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "plan" => {
+                let list = self.list_name(arguments)?;
+                let todos = self.plan(&list)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "render_markdown" => {
+                let list = self.list_name(arguments)?;
+                let MaxTextLenArgs { max_text_len } = parse_args(arguments)?;
+                let todos = self.get_todos(&list)?;
+                Ok(vec![Content::text(render_markdown(
+                    &list,
+                    &todos,
+                    max_text_len,
+                ))])
+            }
+            "summarize" => {
+                let list = self.list_name(arguments)?;
+                let MaxTextLenArgs { max_text_len } = parse_args(arguments)?;
+                let todos = self.get_todos(&list)?;
+                Ok(vec![Content::text(summarize(&todos, max_text_len))])
+            }
+            "store_stats" => Ok(vec![Content::text(self.to_json(&self.store_stats()?)?)]),
+            "list_tags" => {
+                let list = self.list_name(arguments)?;
+                let tags = self.list_tags(&list)?;
+                Ok(vec![Content::text(self.to_json(&tags)?)])
+            }
+            "complete_matching" => {
+                let list = self.list_name(arguments)?;
+                let FilterArgs { filter } = parse_args(arguments)?;
+                let query = TodoQuery {
+                    done: Some(false),
+                    ..filter
+                };
+                let DryRunArgs { dry_run } = parse_args(arguments)?;
+                let todos = self.complete_matching(&list, &query, dry_run)?;
+                let result = serde_json::json!({
+                    "count": todos.len(),
+                    "dry_run": dry_run,
+                    "todos": todos,
+                });
+                Ok(vec![Content::text(self.to_json(&result)?)])
+            }
+            "recent_activity" => {
+                let list = self.list_name(arguments)?;
+                let CountArgs { count } = parse_args(arguments)?;
+                let count = match count {
+                    None => DEFAULT_RECENT_ACTIVITY,
+                    Some(count) if (1..=MAX_RECENT_ACTIVITY).contains(&count) => count,
+                    Some(_) => return Err(ToolError::InvalidParameters("count".to_string())),
+                };
+                let activity = self.recent_activity(&list, count)?;
+                Ok(vec![Content::text(self.to_json(&activity)?)])
+            }
+            "bump_todo" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let todo = self.modify_todo(&list, id, |_| Ok(()))?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "auto_prioritize" => {
+                let list = self.list_name(arguments)?;
+                let rules: PriorityRules = parse_args(arguments)?;
+                if rules.medium_within_days < rules.high_within_days {
+                    return Err(ToolError::InvalidParameters(
+                        "medium_within_days must not be less than high_within_days".to_string(),
+                    ));
+                }
+                let AutoPrioritizeArgs { respect_manual } = parse_args(arguments)?;
+                let changed = self.auto_prioritize(&list, rules, respect_manual)?;
+                Ok(vec![Content::text(
+                    self.to_json(&serde_json::json!({ "changed": changed }))?,
+                )])
+            }
+            "due_on_weekday" => {
+                let list = self.list_name(arguments)?;
+                let WeekdayArgs { weekday } = parse_args(arguments)?;
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let todos = self.due_on_weekday(&list, weekday, offset)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "needs_triage" => {
+                let list = self.list_name(arguments)?;
+                let TriageArgs {
+                    missing_due_date,
+                    missing_priority,
+                } = parse_args(arguments)?;
+                if !missing_due_date && !missing_priority {
+                    return Err(ToolError::InvalidParameters(
+                        "at least one of missing_due_date and missing_priority must be true"
+                            .to_string(),
+                    ));
+                }
+                let todos = self.needs_triage(&list, missing_due_date, missing_priority)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "get_list_settings" => {
+                let list = self.list_name(arguments)?;
+                let settings = self.get_list_settings(&list)?;
+                Ok(vec![Content::text(self.to_json(&settings)?)])
+            }
+            "set_list_settings" => {
+                let list = self.list_name(arguments)?;
+                let ListSettingsArgs { default_due_offset } = parse_args(arguments)?;
+                let mut settings = self.get_list_settings(&list)?;
+                if let Some(offset) = default_due_offset {
+                    settings.default_due_offset = offset;
+                }
+                self.save_list_settings(&list, &settings)?;
+                Ok(vec![Content::text(self.to_json(&settings)?)])
+            }
+            "export_table" => {
+                let list = self.list_name(arguments)?;
+                let TableArgs { columns } = parse_args(arguments)?;
+                let todos = self.get_todos(&list)?;
+                Ok(vec![Content::text(render_table(&todos, &columns))])
+            }
+            "store_info" => Ok(vec![Content::text(self.to_json(&self.store_info()?)?)]),
+            "todo_progress" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let progress = self.todo_progress(&list, id)?;
+                Ok(vec![Content::text(self.to_json(&progress)?)])
+            }
+            "set_focus" => {
+                let list = self.list_name(arguments)?;
+                let IdArgs { id } = parse_args(arguments)?;
+                let todo = self.set_focus(&list, id)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "get_focus" => {
+                let list = self.list_name(arguments)?;
+                let todo = self.get_focus(&list)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "clear_focus" => {
+                let list = self.list_name(arguments)?;
+                self.clear_focus(&list)?;
+                Ok(vec![Content::text("".to_string())])
+            }
+            "created_histogram" => {
+                let list = self.list_name(arguments)?;
+                let HistogramArgs { from, to } = parse_args(arguments)?;
+                if let (Some(from), Some(to)) = (from, to) {
+                    if from > to {
+                        return Err(ToolError::InvalidParameters("from".to_string()));
+                    }
+                }
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let histogram = self.created_histogram(&list, from, to, offset)?;
+                Ok(vec![Content::text(self.to_json(&histogram)?)])
+            }
+            "upsert_todo" => {
+                let list = self.list_name(arguments)?;
+                let upsert: Upsert = parse_args(arguments)?;
+                let (todo, created) = self.upsert_todo(&list, upsert)?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "created": created, "todo": todo }),
+                )?)])
+            }
+            "oldest_open" => {
+                let list = self.list_name(arguments)?;
+                let LimitArgs { limit } = parse_args(arguments)?;
+                let todos = self.oldest_open(&list, limit)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "check_due_date" => {
+                let CheckDueDateArgs { due_date: due } = parse_args(arguments)?;
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let check = self.check_due_date(due, offset);
+                Ok(vec![Content::text(self.to_json(&check)?)])
+            }
+            "list_ids" => {
+                let list = self.list_name(arguments)?;
+                let QueryArgs { filter } = parse_args(arguments)?;
+                let ids: Vec<u64> = self
+                    .query_todos(&list, &filter.unwrap_or_default())?
+                    .iter()
+                    .map(|todo| todo.id)
+                    .collect();
+                let ids = if self.string_ids {
+                    serde_json::json!(ids.iter().map(u64::to_string).collect::<Vec<_>>())
+                } else {
+                    serde_json::json!(ids)
+                };
+                Ok(vec![Content::text(self.to_json(&ids)?)])
+            }
+            "random_todo" => {
+                let list = self.list_name(arguments)?;
+                let RandomArgs {
+                    tags,
+                    priority,
+                    seed,
+                } = parse_args(arguments)?;
+                let query = TodoQuery {
+                    done: Some(false),
+                    tags,
+                    priority,
+                    ..Default::default()
+                };
+                let todo = self.random_todo(&list, &query, seed)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "promote_subtask" => {
+                let list = self.list_name(arguments)?;
+                let SubtaskArgs { id, index } = parse_args(arguments)?;
+                let todo = self.promote_subtask(&list, id, index)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "related_todos" => {
+                let list = self.list_name(arguments)?;
+                let RelatedArgs { id, count } = parse_args(arguments)?;
+                let count = match count {
+                    None => DEFAULT_RELATED_TODOS,
+                    Some(count) if (1..=MAX_RELATED_TODOS).contains(&count) => count,
+                    Some(_) => return Err(ToolError::InvalidParameters("count".to_string())),
+                };
+                let todos = self.related_todos(&list, id, count)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "week_view" => {
+                let list = self.list_name(arguments)?;
+                let week_start = match parse_args::<WeekViewArgs>(arguments)?.week_start {
+                    WeekStart::Monday => Weekday::Mon,
+                    WeekStart::Sunday => Weekday::Sun,
+                };
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let view = self.week_view(&list, week_start, offset)?;
+                Ok(vec![Content::text(self.to_json(&view)?)])
+            }
+            "prioritize_matching" => {
+                let list = self.list_name(arguments)?;
+                let FilterArgs { filter } = parse_args(arguments)?;
+                let PrioritizeArgs { priority } = parse_args(arguments)?;
+                let DryRunArgs { dry_run } = parse_args(arguments)?;
+                let todos = self.prioritize_matching(&list, &filter, priority, dry_run)?;
+                let result = serde_json::json!({
+                    "count": todos.len(),
+                    "dry_run": dry_run,
+                    "todos": todos,
+                });
+                Ok(vec![Content::text(self.to_json(&result)?)])
+            }
+            "set_assignee" => {
+                let list = self.list_name(arguments)?;
+                let AssigneeArgs { id, assignee } = parse_args(arguments)?;
+                let todo = self.set_assignee(&list, id, assignee)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "workload_by_assignee" => {
+                let list = self.list_name(arguments)?;
+                let workloads = self.workload_by_assignee(&list)?;
+                Ok(vec![Content::text(self.to_json(&workloads)?)])
+            }
+            "archive_list" => {
+                let NameArgs { name } = parse_args(arguments)?;
+                let count = self.archive_list(&name)?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "archived": name, "todos": count }),
+                )?)])
+            }
+            "restore_list" => {
+                let NameArgs { name } = parse_args(arguments)?;
+                let count = self.restore_list(&name)?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "restored": name, "todos": count }),
+                )?)])
+            }
+            "most_overdue" => {
+                let list = self.list_name(arguments)?;
+                let todo = self.most_overdue(&list)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "move_todos" => {
+                let MoveArgs { ids, from, to } = parse_args(arguments)?;
+                let result = self.move_todos(&ids, &from, &to, false)?;
+                Ok(vec![Content::text(self.to_json(&result)?)])
+            }
+            "overloaded_days" => {
+                let list = self.list_name(arguments)?;
+                let OverloadedArgs { max_per_day } = parse_args(arguments)?;
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let days = self.overloaded_days(&list, max_per_day, offset)?;
+                Ok(vec![Content::text(self.to_json(&days)?)])
+            }
+            "reopen_recent" => {
+                let list = self.list_name(arguments)?;
+                let ReopenArgs {
+                    within_hours,
+                    dry_run,
+                } = parse_args(arguments)?;
+                let todos = self.reopen_recent(&list, within_hours, dry_run)?;
+                let result = serde_json::json!({
+                    "count": todos.len(),
+                    "dry_run": dry_run,
+                    "todos": todos,
+                });
+                Ok(vec![Content::text(self.to_json(&result)?)])
+            }
+            "schema_status" => Ok(vec![Content::text(self.to_json(&self.schema_status()?)?)]),
+            "export_snapshot" => {
+                let list = self.list_name(arguments)?;
+                let bundle = self.export_snapshot(&list)?;
+                Ok(vec![Content::text(self.to_json(&bundle)?)])
+            }
+            "import_todos" => {
+                let list = self.list_name(arguments)?;
+                let ImportArgs { bundle } = parse_args(arguments)?;
+                let todos = self.import_todos(&list, &bundle)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "find_by_id_prefix" => {
+                let list = self.list_name(arguments)?;
+                let PrefixArgs { prefix, unique } = parse_args(arguments)?;
+                let todos = self.find_by_id_prefix(&list, &prefix, unique)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "forecast" => {
+                let list = self.list_name(arguments)?;
+                let ForecastArgs { window_days } = parse_args(arguments)?;
+                let window_days = window_days.unwrap_or(DEFAULT_FORECAST_WINDOW_DAYS);
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let forecast = self.forecast(&list, window_days, offset)?;
+                Ok(vec![Content::text(self.to_json(&forecast)?)])
+            }
+            "set_list" => {
+                let list = self.list_name(arguments)?;
+                let SetListArgs { id, to, create } = parse_args(arguments)?;
+                let todo = self.set_list(&list, id, &to, create)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "tag_matching" => {
+                let list = self.list_name(arguments)?;
+                let FilterArgs { filter } = parse_args(arguments)?;
+                let tags = match parse_args(arguments)? {
+                    TagMatchingArgs {
+                        tag: None,
+                        tags: None,
+                    } => return Err(ToolError::InvalidParameters("tags".to_string())),
+                    TagMatchingArgs {
+                        tag: Some(tag),
+                        tags: None,
+                    } => vec![tag],
+                    TagMatchingArgs {
+                        tag: None,
+                        tags: Some(tags),
+                    } => tags,
+                    TagMatchingArgs { .. } => {
+                        return Err(ToolError::InvalidParameters(
+                            "pass either tag or tags, not both".to_string(),
+                        ))
+                    }
+                };
+                let DryRunArgs { dry_run } = parse_args(arguments)?;
+                let todos = self.tag_matching(&list, &filter, &tags, dry_run)?;
+                let result = serde_json::json!({
+                    "count": todos.len(),
+                    "dry_run": dry_run,
+                    "todos": todos,
+                });
+                Ok(vec![Content::text(self.to_json(&result)?)])
+            }
+            "diff_snapshots" => {
+                let list = self.list_name(arguments)?;
+                let DiffArgs { from, to } = parse_args(arguments)?;
+                let to = to.unwrap_or_else(|| CURRENT_SNAPSHOT.to_string());
+                let diff = self.diff_snapshots(&list, &from, &to)?;
+                Ok(vec![Content::text(self.to_json(&diff)?)])
+            }
+            "tag_cooccurrence" => {
+                let list = self.list_name(arguments)?;
+                let CountArgs { count } = parse_args(arguments)?;
+                let count = match count {
+                    None => DEFAULT_TAG_PAIRS,
+                    Some(count) if (1..=MAX_TAG_PAIRS).contains(&count) => count,
+                    Some(_) => return Err(ToolError::InvalidParameters("count".to_string())),
+                };
+                let pairs = self.tag_cooccurrence(&list, count)?;
+                Ok(vec![Content::text(self.to_json(&pairs)?)])
+            }
+            "wip_status" => {
+                let list = self.list_name(arguments)?;
+                let WipArgs { limit, tag } = parse_args(arguments)?;
+                let tag = tag.unwrap_or_else(|| DEFAULT_WIP_TAG.to_string());
+                let status = self.wip_status(&list, tag, limit)?;
+                Ok(vec![Content::text(self.to_json(&status)?)])
+            }
+            "set_status" => {
+                let list = self.list_name(arguments)?;
+                let StatusArgs { id, status } = parse_args(arguments)?;
+                let todo = self.set_status(&list, id, status)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "import_outline" => {
+                let list = self.list_name(arguments)?;
+                let OutlineArgs { text } = parse_args(arguments)?;
+                let (todos, subtasks) = self.import_outline(&list, &text)?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "todos": todos, "subtasks": subtasks }),
+                )?)])
+            }
+            "extract_links" => {
+                let list = self.list_name(arguments)?;
+                let links = self.extract_links(&list)?;
+                Ok(vec![Content::text(self.to_json(&links)?)])
+            }
+            "done_today" => {
+                let list = self.list_name(arguments)?;
+                let offset = parse_args::<TimezoneArgs>(arguments)?.offset();
+                let todos = self.done_today(&list, offset)?;
+                Ok(vec![Content::text(self.to_json(&todos)?)])
+            }
+            "set_reminder_schedule" => {
+                let list = self.list_name(arguments)?;
+                let ScheduleArgs {
+                    id,
+                    every,
+                    starts_at,
+                } = parse_args(arguments)?;
+                let todo = self.set_reminder_schedule(&list, id, every, starts_at)?;
+                Ok(vec![Content::text(self.to_json(&todo)?)])
+            }
+            "get_lists" => {
+                let GetListsArgs { include_archived } = parse_args(arguments)?;
+                let lists = self.get_lists()?;
+                if !include_archived {
+                    return Ok(vec![Content::text(self.to_json(&lists)?)]);
+                }
+                let archived = self.archived_lists()?;
+                Ok(vec![Content::text(self.to_json(
+                    &serde_json::json!({ "lists": lists, "archived": archived }),
+                )?)])
+            }
+            _ => Err(ToolError::NotFound(tool_name.to_string())),
+        }
+    }
+
+    /// Posts a description of a successful mutation to `TODO_WEBHOOK_URL`, if
+    /// set. Delivery happens on a spawned task so a slow or failing endpoint
+    /// never delays the tool call.
+    fn notify_webhook(&self, tool_name: &str, arguments: &Value, content: &[Content]) {
+        let Some(url) = self.webhook_url.clone() else {
+            return;
+        };
+        if !MUTATING_TOOLS.contains(&tool_name) {
+            return;
+        }
+        let payload = serde_json::json!({
+            "tool": tool_name,
+            "arguments": arguments,
+            "result": content,
+            "timestamp": Utc::now().to_rfc3339(),
+        });
+        let client = self.http.clone();
+        tauri::async_runtime::spawn(send_webhook(client, url, payload));
+    }
+}
+
+impl<R: Runtime> mcp_server::Router for TodoRouter<R> {
+    fn name(&self) -> String {
+        "todo".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        if let Some(instructions) = &self.instructions {
+            return instructions.clone();
+        }
+        concat!(
+            "This server manages todos with persistent storage. Each tool's description ",
+            "says what it does; this overview covers what they share. Every tool accepts ",
+            "an optional `list` name to work on a list other than the default one, and ",
+            "`get_lists` returns the names of all lists. Wherever a tool takes an `id`, ",
+            "the todo's short per-list reference `#<number>` works as well. Dates are ",
+            "RFC3339 timestamps. Tools that return many todos page with `offset`/`limit`. ",
+            "Tools whose schema has `\"destructive\": true` discard data; confirm with the ",
+            "user before calling them, and preview the ones that take `dry_run` first.",
+        )
+        .to_string()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new()
+            .with_tools(false)
+            .with_resources(false, false)
+            .with_prompts(false)
+            .build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        vec![
+            Tool::new(
+                "get_todos".to_string(),
+                "Get Todos".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "offset": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Number of todos to skip"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "include_summary": {
+                            "type": "boolean",
+                            "description": "Also return a plain-text summary as a separate first content block"
+                        },
+                        "chunk_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Split the todos over several content blocks of at most this many todos, each a JSON array, for clients that reassemble them. Can't be combined with offset or limit; without it the list is one JSON array"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "add_todo".to_string(),
+                "Add Todo".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "text": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Todo text"
+                        },
+                        "due_date": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "Due date as an RFC3339 timestamp"
+                        },
+                        "priority": {
+                            "type": "string",
+                            "enum": ["low", "medium", "high"],
+                            "description": "Priority level"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Tags; blank entries are rejected"
+                        },
+                        "done": {
+                            "type": "boolean",
+                            "description": "Create the todo already completed; defaults to false"
+                        },
+                        "position": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Index to insert at; out-of-range values go to the end"
+                        },
+                        "return_list": {
+                            "type": "boolean",
+                            "description": "Also return the whole updated list; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["text"]
+                }),
+            ),
+            Tool::new(
+                "remove_todo".to_string(),
+                "Remove Todo".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "return_list": {
+                            "type": "boolean",
+                            "description": "Also return the whole updated list; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "update_todo".to_string(),
+                "Update Todo".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "text": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Todo text"
+                        },
+                        "done": {
+                            "type": "boolean",
+                            "description": "Whether the todo is completed"
+                        },
+                        "return_list": {
+                            "type": "boolean",
+                            "description": "Also return the whole updated list; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "text", "done"]
+                }),
+            ),
+            Tool::new(
+                "set_due_date".to_string(),
+                "Set a todo's due date, an RFC3339 timestamp.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "due_date": {
+                            "type": ["string", "null"],
+                            "format": "date-time",
+                            "description": "Due date as an RFC3339 timestamp; null clears it"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "due_date"]
+                }),
+            ),
+            Tool::new(
+                "reschedule_overdue".to_string(),
+                "Move every overdue open todo to a new due date, the end of today by default.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "to": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "New due date as an RFC3339 timestamp; defaults to the end of today"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_priority".to_string(),
+                "Set a todo's priority to `low`, `medium` or `high`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "priority": {
+                            "type": ["string", "null"],
+                            "enum": ["low", "medium", "high", null],
+                            "description": "Priority level; null clears it"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "priority"]
+                }),
+            ),
+            Tool::new(
+                "agenda".to_string(),
+                "List what needs attention on a given day.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "date": {
+                            "type": "string",
+                            "format": "date",
+                            "description": "Day as YYYY-MM-DD; defaults to today"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "add_subtask".to_string(),
+                "Add a subtask to a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "text": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Subtask text"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "text"]
+                }),
+            ),
+            Tool::new(
+                "set_subtask_done".to_string(),
+                "Mark one of a todo's subtasks as done or not done.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "index": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Zero-based subtask index"
+                        },
+                        "done": {
+                            "type": "boolean",
+                            "description": "Whether the subtask is completed"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "index", "done"]
+                }),
+            ),
+            Tool::new(
+                "toggle_all_subtasks".to_string(),
+                "Mark every subtask of a todo as done or not done.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "done": {
+                            "type": "boolean",
+                            "description": "Done state to apply to every subtask"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "done"]
+                }),
+            ),
+            Tool::new(
+                "open_todos".to_string(),
+                "Get only the open todos, in list order.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "offset": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Number of todos to skip"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "include_summary": {
+                            "type": "boolean",
+                            "description": "Also return a plain-text summary as a separate first content block"
+                        },
+                        "chunk_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Split the todos over several content blocks of at most this many todos, each a JSON array, for clients that reassemble them. Can't be combined with offset or limit; without it the list is one JSON array"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "done_todos".to_string(),
+                "Get only the completed todos, in list order.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "offset": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Number of todos to skip"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "include_summary": {
+                            "type": "boolean",
+                            "description": "Also return a plain-text summary as a separate first content block"
+                        },
+                        "chunk_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Split the todos over several content blocks of at most this many todos, each a JSON array, for clients that reassemble them. Can't be combined with offset or limit; without it the list is one JSON array"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_estimate".to_string(),
+                "Set how many minutes a todo is expected to take.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "minutes": {
+                            "type": ["integer", "null"],
+                            "minimum": 0,
+                            "description": "Estimate in minutes; null clears it"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "minutes"]
+                }),
+            ),
+            Tool::new(
+                "log_time".to_string(),
+                "Add minutes spent on a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "minutes": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Minutes spent to add to the total"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "minutes"]
+                }),
+            ),
+            Tool::new(
+                "workload".to_string(),
+                "Sum the estimated, spent and remaining minutes of the open todos.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_notes".to_string(),
+                "Attach free-form notes to a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "notes": {
+                            "type": ["string", "null"],
+                            "description": "Free-form notes; null clears them"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "notes"]
+                }),
+            ),
+            Tool::new(
+                "export_ics".to_string(),
+                "Render the todos with due dates, or all of them with `include_all`, as an iCalendar document.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "include_all": {
+                            "type": "boolean",
+                            "description": "Include completed todos"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "list_todos_changed_since".to_string(),
+                "List the todos changed since a timestamp and the ids deleted since then. Pass the `server_time` of the previous call as `since` to keep a mirror in sync.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "since": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "RFC3339 timestamp, usually the server_time of the previous call"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["since"]
+                }),
+            ),
+            Tool::new(
+                "set_tags".to_string(),
+                "Replace a todo's tags.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Replacement tags; blank entries are rejected"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "tags"]
+                }),
+            ),
+            Tool::new(
+                "retag".to_string(),
+                "Rename a tag on every todo of a list.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "from_tag": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Tag to rename"
+                        },
+                        "to_tag": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Replacement tag"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["from_tag", "to_tag"]
+                }),
+            ),
+            Tool::new(
+                "remove_tag_everywhere".to_string(),
+                "Drop a tag from every todo of a list.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tag": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Tag to remove"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["tag"]
+                }),
+            ),
+            Tool::new(
+                "create_snapshot".to_string(),
+                "Save a named checkpoint of a list, e.g. before a large reorganization, that `restore_snapshot` can roll back to.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Snapshot name"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                "list_snapshots".to_string(),
+                "List the snapshots saved for a list.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "restore_snapshot".to_string(),
+                "Roll a list back to a snapshot made with `create_snapshot`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Snapshot name"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                "revert_todo".to_string(),
+                "Undo the most recent edits to a single todo's text, done state or priority.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "sort_todos".to_string(),
+                "Return a sorted view of a list by text (with locale-aware collation), due date, creation time or priority. The stored order is left alone.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "by": {
+                            "type": "string",
+                            "enum": ["text", "due_date", "created_at", "priority"],
+                            "description": "Field to sort by"
+                        },
+                        "order": {
+                            "type": "string",
+                            "enum": ["asc", "desc"],
+                            "description": "Sort direction"
+                        },
+                        "locale": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "BCP 47 locale for text collation, e.g. \"de\" or \"sv\""
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Number of todos to skip"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "include_summary": {
+                            "type": "boolean",
+                            "description": "Also return a plain-text summary as a separate first content block"
+                        },
+                        "chunk_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Split the todos over several content blocks of at most this many todos, each a JSON array, for clients that reassemble them. Can't be combined with offset or limit; without it the list is one JSON array"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["by"]
+                }),
+            ),
+            Tool::new(
+                "stale_todos".to_string(),
+                "Find open todos nobody has touched for a given number of days.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "older_than_days": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Minimum number of days since the last update"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["older_than_days"]
+                }),
+            ),
+            Tool::new(
+                "metrics".to_string(),
+                "Report how often each tool was called and how long the calls took.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "reset_metrics".to_string(),
+                "Clear the counters reported by `metrics`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "split_todo".to_string(),
+                "Replace a todo with several new ones that keep its tags and priority.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "texts": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "minLength": 1
+                            },
+                            "minItems": 1,
+                            "description": "Texts of the todos that replace the original"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "texts"]
+                }),
+            ),
+            Tool::new(
+                "streak".to_string(),
+                "Report the current and longest run of days with at least one completed todo. Pass `timezone` as a UTC offset like `+09:00` to set the day boundaries.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "compact".to_string(),
+                "Move completed todos below the open ones without changing the order within either group.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "add_attachment".to_string(),
+                "Attach a link or file path to a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "attachment": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "URI or file path to attach"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "attachment"]
+                }),
+            ),
+            Tool::new(
+                "remove_attachment".to_string(),
+                "Remove a link or file path from a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "attachment": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Attachment to remove, exactly as stored"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "attachment"]
+                }),
+            ),
+            Tool::new(
+                "query_todos".to_string(),
+                "Find the todos matching filters on done state, status, tags, priority, text and due date, with optional sorting and paging. All given filters must match.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": filter_schema("Filters that must all match; every todo matches when omitted"),
+                        "sort": {
+                            "type": "object",
+                            "properties": {
+                                "by": {
+                                    "type": "string",
+                                    "enum": ["text", "due_date", "created_at", "priority"],
+                                    "description": "Field to sort by"
+                                },
+                                "order": {
+                                    "type": "string",
+                                    "enum": ["asc", "desc"],
+                                    "description": "Sort direction"
+                                },
+                                "locale": {
+                                    "type": "string",
+                                    "minLength": 1,
+                                    "description": "BCP 47 locale for text collation, e.g. \"de\" or \"sv\""
+                                }
+                            },
+                            "required": ["by"],
+                            "description": "Sort applied to the matches; list order when omitted"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Number of todos to skip"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "include_summary": {
+                            "type": "boolean",
+                            "description": "Also return a plain-text summary as a separate first content block"
+                        },
+                        "chunk_size": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Split the todos over several content blocks of at most this many todos, each a JSON array, for clients that reassemble them. Can't be combined with offset or limit; without it the list is one JSON array"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "add_reminder".to_string(),
+                "Add a reminder to a todo. Reminders are separate from due dates.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "at": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "When to be reminded, as an RFC3339 timestamp"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "at"]
+                }),
+            ),
+            Tool::new(
+                "remove_reminder".to_string(),
+                "Remove a reminder from a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "at": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "Reminder time to remove, as an RFC3339 timestamp"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "at"]
+                }),
+            ),
+            Tool::new(
+                "due_reminders".to_string(),
+                "List the open todos whose reminders have fired, including occurrences of repeating reminders.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "dismiss_reminders".to_string(),
+                "Silence the fired reminders of a todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "create_list".to_string(),
+                "Create an empty list up front. The number of lists is capped.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Name of the new list"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                "normalize_todos".to_string(),
+                "Clean up todo texts with opt-in trimming, space collapsing, title casing and trailing punctuation removal.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "trim": {
+                            "type": "boolean",
+                            "description": "Remove leading and trailing whitespace"
+                        },
+                        "collapse_spaces": {
+                            "type": "boolean",
+                            "description": "Replace runs of spaces with a single space"
+                        },
+                        "title_case": {
+                            "type": "boolean",
+                            "description": "Upper-case the first letter of every word"
+                        },
+                        "strip_trailing_punctuation": {
+                            "type": "boolean",
+                            "description": "Remove trailing periods, commas, colons, semicolons, question and exclamation marks and ellipses"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_dependencies".to_string(),
+                "Record which todos must be done before another.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "depends_on": {
+                            "type": "array",
+                            "items": {
+                                "type": "integer",
+                                "minimum": 0
+                            },
+                            "description": "Ids of todos in the same list that must be done first; an empty array clears them"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "depends_on"]
+                }),
+            ),
+            Tool::new(
+                "plan".to_string(),
+                "Return the open todos in an order that respects their dependencies. Fails with the members of the cycle if there is one.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "render_markdown".to_string(),
+                "Render a list as a Markdown checklist. `max_text_len` shortens long texts.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "max_text_len": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Truncate each text to this many characters, ending in an ellipsis; no truncation when omitted"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "summarize".to_string(),
+                "Give the counts of a list plus its open todos. `max_text_len` shortens long texts.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "max_text_len": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Truncate each text to this many characters, ending in an ellipsis; no truncation when omitted"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "store_stats".to_string(),
+                "Report the size of the storage file, and flag when it has grown large enough that completed todos should be cleared.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "list_tags".to_string(),
+                "List the tags in use with how many todos carry each. Check it before inventing new tags.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "complete_matching".to_string(),
+                "Mark every open todo matching `filter` as done. Completed todos never match, whatever the filter says about `done`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": filter_schema("Filters that must all match; pass {} to match every todo"),
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Return the todos that would be completed without changing anything"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["filter"]
+                }),
+            ),
+            Tool::new(
+                "recent_activity".to_string(),
+                "Show the most recently created or modified todos, the quickest way to see what changed.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "count": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 100,
+                            "description": "Number of todos to return; defaults to 10"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "bump_todo".to_string(),
+                "Mark a todo as touched without changing it, keeping it out of `stale_todos`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "auto_prioritize".to_string(),
+                "Assign priorities to the open todos from their due dates and age. Pass `respect_manual` to keep priorities the user chose.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "high_within_days": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Overdue todos and those due within this many days become high; defaults to 2"
+                        },
+                        "medium_within_days": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todos due within this many days become medium; defaults to 7"
+                        },
+                        "old_after_days": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todos without a due date created more than this many days ago become medium; defaults to 30"
+                        },
+                        "respect_manual": {
+                            "type": "boolean",
+                            "description": "Leave todos whose priority was set by the user unchanged"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "due_on_weekday".to_string(),
+                "List the open todos due on a given day of the week.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "weekday": {
+                            "type": "string",
+                            "enum": ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"],
+                            "description": "Day of the week"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["weekday"]
+                }),
+            ),
+            Tool::new(
+                "needs_triage".to_string(),
+                "Find the open todos that still lack a due date or priority.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "missing_due_date": {
+                            "type": "boolean",
+                            "description": "Count a missing due date as needing triage; defaults to true"
+                        },
+                        "missing_priority": {
+                            "type": "boolean",
+                            "description": "Count a missing priority as needing triage; defaults to true"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "get_list_settings".to_string(),
+                "Get a list's options, such as `default_due_offset`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_list_settings".to_string(),
+                "Set a list's options. `default_due_offset` gives new todos without an explicit due date a deadline relative to their creation; an explicit due date always wins.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "default_due_offset": {
+                            "type": ["string", "null"],
+                            "description": "Due date offset for new todos without an explicit due date, e.g. \"+3 days\", \"12h\" or \"1 week\"; null turns it off"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "export_table".to_string(),
+                "Render todos as a GitHub-flavored Markdown table for pasting into issues or pull requests.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "columns": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "enum": ["id", "text", "status", "priority", "due_date"]
+                            },
+                            "minItems": 1,
+                            "description": "Columns to include, in order; defaults to all of them"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "store_info".to_string(),
+                "Tell where the data is kept on disk.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {},
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "todo_progress".to_string(),
+                "Report how many of a todo's subtasks are done.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "set_focus".to_string(),
+                "Record the one todo being worked on right now.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "get_focus".to_string(),
+                "Get the todo set with `set_focus`, or null when there is none.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "clear_focus".to_string(),
+                "Drop the focus set with `set_focus`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "created_histogram".to_string(),
+                "Count todos per day of creation, optionally between `from` and `to`, for activity heatmaps.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "from": {
+                            "type": "string",
+                            "format": "date",
+                            "description": "First day to count as YYYY-MM-DD; unbounded when omitted"
+                        },
+                        "to": {
+                            "type": "string",
+                            "format": "date",
+                            "description": "Last day to count as YYYY-MM-DD; unbounded when omitted"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "upsert_todo".to_string(),
+                "Update a todo by id, or create it when the id is unknown or omitted, which suits clients pushing a desired state.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": MAX_TODO_ID,
+                            "description": "Todo id; a todo is created (keeping this id) when it doesn't exist or when omitted"
+                        },
+                        "text": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Todo text"
+                        },
+                        "done": {
+                            "type": "boolean",
+                            "description": "Done state; unchanged on update and false on create when omitted"
+                        },
+                        "due_date": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "Due date as an RFC3339 timestamp; unchanged on update when omitted"
+                        },
+                        "priority": {
+                            "type": "string",
+                            "enum": ["low", "medium", "high"],
+                            "description": "Priority level; unchanged on update when omitted"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Tags replacing the current ones; blank entries are rejected"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["text"]
+                }),
+            ),
+            Tool::new(
+                "oldest_open".to_string(),
+                "List the open todos that have lingered longest, oldest first.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Maximum number of todos to return"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "check_due_date".to_string(),
+                "Check whether a proposed due date is in the past, today or the future before calling `set_due_date`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "due_date": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Proposed due date as an RFC3339 timestamp"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        }
+                    },
+                    "required": ["due_date"]
+                }),
+            ),
+            Tool::new(
+                "list_ids".to_string(),
+                "Take the same filters as `query_todos` but return only the ids, which is cheapest for iterating or checking existence.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": filter_schema("Filters that must all match; every todo matches when omitted"),
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "random_todo".to_string(),
+                "Pick a random open todo, optionally among those with given tags or priority.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Only pick among todos carrying every one of these tags"
+                        },
+                        "priority": {
+                            "type": "string",
+                            "enum": ["low", "medium", "high"],
+                            "description": "Only pick among todos with this priority"
+                        },
+                        "seed": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Seed for a reproducible pick; random when omitted"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "promote_subtask".to_string(),
+                "Turn a subtask that has grown into its own task into a separate todo.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Parent todo id"
+                        },
+                        "index": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Zero-based index of the subtask to promote"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "index"]
+                }),
+            ),
+            Tool::new(
+                "related_todos".to_string(),
+                "Find other todos sharing tags with a given one, most shared tags first.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "count": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 100,
+                            "description": "Maximum number of todos to return; defaults to 10"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            ),
+            Tool::new(
+                "week_view".to_string(),
+                "Group the open todos due this week by weekday, plus an `overdue` bucket.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "week_start": {
+                            "type": "string",
+                            "enum": ["monday", "sunday"],
+                            "description": "First day of the week; defaults to monday"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
             Tool::new(
-                "get_todos".to_string(),
-                "Get Todos".to_string(),
+                "prioritize_matching".to_string(),
+                "Assign one priority to every todo matching a filter with the same fields as `query_todos`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": filter_schema("Filters that must all match; pass {} to match every todo"),
+                        "priority": {
+                            "type": "string",
+                            "enum": ["low", "medium", "high"],
+                            "description": "Priority to assign"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Return the todos that would be changed without changing anything"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["filter", "priority"]
+                }),
+            ),
+            Tool::new(
+                "set_assignee".to_string(),
+                "Assign a todo to someone.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "assignee": {
+                            "type": ["string", "null"],
+                            "minLength": 1,
+                            "description": "Who the todo is assigned to; null unassigns it"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "assignee"]
+                }),
+            ),
+            Tool::new(
+                "workload_by_assignee".to_string(),
+                "Show how the open work is spread across assignees.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "archive_list".to_string(),
+                "Put a finished list away without deleting it, together with its settings, focus and snapshots. Archived lists only show up in `get_lists` with `include_archived`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Name of the list to archive"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                "restore_list".to_string(),
+                "Bring a list put away with `archive_list` back under its old name.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Name of the archived list"
+                        }
+                    },
+                    "required": ["name"]
+                }),
+            ),
+            Tool::new(
+                "most_overdue".to_string(),
+                "Return the single open todo the user is furthest behind on.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "move_todos".to_string(),
+                "Move several todos from one list to another in a single operation. Each list is written once. Ids not found in the source list are reported rather than failing the call. The destination list must already exist and must not hold any of the ids.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "ids": {
+                            "type": "array",
+                            "items": {
+                                "type": "integer"
+                            },
+                            "description": "Ids of the todos to move"
+                        },
+                        "from": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List to move the todos out of"
+                        },
+                        "to": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Existing list to move the todos into"
+                        }
+                    },
+                    "required": ["ids", "from", "to"]
+                }),
+            ),
+            Tool::new(
+                "overloaded_days".to_string(),
+                "Find the days on which more open todos are due than a given maximum, with the todos due on each, so work can be spread out. Days come earliest first.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "max_per_day": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Most open todos that may be due on one day; days with more are returned"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["max_per_day"]
+                }),
+            ),
+            Tool::new(
+                "reopen_recent".to_string(),
+                "Mark todos completed within the last given number of hours as not done again, e.g. to undo an accidental mass completion. Older completions are left alone.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "within_hours": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Reopen todos whose completed_at is at most this many hours ago"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only return the todos that would be reopened; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["within_hours"]
+                }),
+            ),
+            Tool::new(
+                "schema_status".to_string(),
+                "Report the schema version of the stored data, the latest version this server knows, whether a migration is pending, and which version was migrated from at startup. Read-only.".to_string(),
                 serde_json::json!({
                     "type": "object",
                     "properties": {},
@@ -141,51 +7835,350 @@ impl mcp_server::Router for TodoRouter {
                 }),
             ),
             Tool::new(
-                "add_todo".to_string(),
-                "Add Todo".to_string(),
+                "export_snapshot".to_string(),
+                "Export a list as a self-contained JSON bundle, with the server name and generation time, for sharing a read-only copy. `import_todos` reads it back.".to_string(),
                 serde_json::json!({
                     "type": "object",
                     "properties": {
-                        "text": {
-                            "type": "string"
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
                         }
                     },
-                    "required": ["text"]
+                    "required": []
                 }),
             ),
             Tool::new(
-                "remove_todo".to_string(),
-                "Remove Todo".to_string(),
+                "import_todos".to_string(),
+                "Add the todos of a bundle produced by `export_snapshot` to a list. They get new ids and numbers; dependencies between them are kept.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "bundle": {
+                            "type": "object",
+                            "description": "The JSON object returned by export_snapshot"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["bundle"]
+                }),
+            ),
+            Tool::new(
+                "find_by_id_prefix".to_string(),
+                "Find the todos whose id starts with the given digits, like a short git hash. Returns an empty array when nothing matches.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "prefix": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Leading digits of the id"
+                        },
+                        "unique": {
+                            "type": "boolean",
+                            "description": "Fail when more than one todo matches; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["prefix"]
+                }),
+            ),
+            Tool::new(
+                "forecast".to_string(),
+                "Estimate when the open todos will all be done, from the average number of completions per day over a recent window. Returns the assumptions used, and a null date with an explanation when nothing was completed in the window. Relay its `explanation` along with the date.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "window_days": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "How many past days to average completions over; defaults to 14"
+                        },
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for the estimated date; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_list".to_string(),
+                "Move a single todo to another list. The target list must exist unless `create` is true.".to_string(),
                 serde_json::json!({
                     "type": "object",
                     "properties": {
                         "id": {
-                            "type": "integer"
+                            "type": "integer",
+                            "description": "Todo id"
+                        },
+                        "to": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List to move the todo into"
+                        },
+                        "create": {
+                            "type": "boolean",
+                            "description": "Create the target list if it doesn't exist; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
                         }
                     },
-                    "required": ["id"]
+                    "required": ["id", "to"]
                 }),
             ),
             Tool::new(
-                "update_todo".to_string(),
-                "Update Todo".to_string(),
+                "tag_matching".to_string(),
+                "Add a tag, or several, to every todo matching a filter, in a single save. Tags a todo already has are not duplicated. Returns the todos that gained a tag.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "filter": filter_schema("Filters that must all match; pass {} to match every todo"),
+                        "tag": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Tag to add"
+                        },
+                        "tags": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "minLength": 1
+                            },
+                            "description": "Tags to add, instead of tag"
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only return the todos that would be tagged; defaults to false"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["filter"]
+                }),
+            ),
+            Tool::new(
+                "diff_snapshots".to_string(),
+                "Compare two snapshots of a list, or a snapshot with the list as it is now, listing the added, removed and changed todos. Changed todos show each differing field before and after. Comparing against `current` is a good way to review a session's edits.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "from": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Snapshot to compare from, or \"current\" for the list as it is now"
+                        },
+                        "to": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Snapshot to compare to, or \"current\"; defaults to current"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["from"]
+                }),
+            ),
+            Tool::new(
+                "tag_cooccurrence".to_string(),
+                "Find the pairs of tags that appear together on the most todos, with how many todos carry both. Useful for spotting redundant tags or natural groupings.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "count": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "maximum": 100,
+                            "description": "How many pairs to return; defaults to 10"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "wip_status".to_string(),
+                "Report how many open todos are in progress, meaning their status is in_progress or they carry the in-progress tag, against a work-in-progress limit, and flag when the limit is exceeded. Suggest finishing something before starting more when it reports `over_limit`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "limit": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Most todos that should be in progress at once"
+                        },
+                        "tag": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "Tag marking todos as in progress; defaults to in-progress"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["limit"]
+                }),
+            ),
+            Tool::new(
+                "set_status".to_string(),
+                "Set a todo's status to todo, in_progress, blocked or done. Setting done, or leaving it, also updates the todo's done flag; marking a todo done elsewhere sets its status to done. When the server has a work-in-progress limit, starting a todo beyond it fails.".to_string(),
                 serde_json::json!({
                     "type": "object",
                     "properties": {
                         "id": {
-                            "type": "integer"
+                            "type": "integer",
+                            "description": "Todo id"
                         },
+                        "status": {
+                            "type": "string",
+                            "enum": ["todo", "in_progress", "blocked", "done"],
+                            "description": "New status"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "status"]
+                }),
+            ),
+            Tool::new(
+                "import_outline".to_string(),
+                "Create todos from a plain-text outline in a single save. Unindented lines become todos and indented lines become subtasks of the todo above; a line starting with [x] is marked done. Leading - or * bullets are ignored.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
                         "text": {
-                            "type": "string"
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "The outline, one item per line"
                         },
-                        "done": {
-                            "type": "boolean"
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
                         }
                     },
-                    "required": ["id", "text", "done"]
+                    "required": ["text"]
+                }),
+            ),
+            Tool::new(
+                "extract_links".to_string(),
+                "Collect the http(s) URLs in each todo's text and notes. Only todos with at least one link are included.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "done_today".to_string(),
+                "List the todos completed since the start of today, in the order they were completed, for an end-of-day review.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "timezone": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "UTC offset such as \"+09:00\" used for day boundaries; defaults to the server's local offset"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": []
+                }),
+            ),
+            Tool::new(
+                "set_reminder_schedule".to_string(),
+                "Give a todo a reminder that repeats at a fixed interval, independently of its due date, or remove it by passing null for every. Each occurrence shows up in `due_reminders` until dismissed with `dismiss_reminders`.".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "minimum": 0,
+                            "description": "Todo id"
+                        },
+                        "every": {
+                            "type": ["string", "null"],
+                            "minLength": 1,
+                            "description": "Interval such as \"30m\", \"12h\", \"1 day\" or \"2 weeks\"; null removes the schedule"
+                        },
+                        "starts_at": {
+                            "type": "string",
+                            "format": "date-time",
+                            "description": "First occurrence, as an RFC3339 timestamp; defaults to one interval from now"
+                        },
+                        "list": {
+                            "type": "string",
+                            "minLength": 1,
+                            "description": "List name; defaults to the configured default list"
+                        }
+                    },
+                    "required": ["id", "every"]
+                }),
+            ),
+            Tool::new(
+                "get_lists".to_string(),
+                "Get Lists".to_string(),
+                serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "include_archived": {
+                            "type": "boolean",
+                            "description": "Also return the archived lists, as {lists, archived}"
+                        }
+                    },
+                    "required": []
                 }),
             ),
         ]
+        .into_iter()
+        .filter(|tool| self.is_allowed(&tool.name))
+        .map(mark_destructive)
+        .map(describe_output)
+        .map(accept_references)
+        .collect()
     }
 
     fn call_tool(
@@ -198,56 +8191,67 @@ impl mcp_server::Router for TodoRouter {
         let tool_name = tool_name.to_string();
 
         Box::pin(async move {
-            match tool_name.as_str() {
-                "get_todos" => {
-                    let todos = this.get_todos()?;
-                    Ok(vec![Content::text(serde_json::to_string(&todos).unwrap())])
-                }
-                "add_todo" => {
-                    let text = arguments["text"]
-                        .as_str()
-                        .ok_or_else(|| ToolError::InvalidParameters("text".to_string()))?
-                        .to_string();
-                    let todo = this.add_todo(text)?;
-                    Ok(vec![Content::text(serde_json::to_string(&todo).unwrap())])
-                }
-                "remove_todo" => {
-                    let id = arguments["id"]
-                        .as_u64()
-                        .ok_or_else(|| ToolError::InvalidParameters("id".to_string()))?;
-                    this.remove_todo(id)?;
-                    Ok(vec![Content::text("".to_string())])
-                }
-                "update_todo" => {
-                    let id = arguments["id"]
-                        .as_u64()
-                        .ok_or_else(|| ToolError::InvalidParameters("id".to_string()))?;
-                    let text = arguments["text"]
-                        .as_str()
-                        .ok_or_else(|| ToolError::InvalidParameters("text".to_string()))?
-                        .to_string();
-                    let done = arguments["done"]
-                        .as_bool()
-                        .ok_or_else(|| ToolError::InvalidParameters("done".to_string()))?;
-                    let todo = Todo { id, text, done };
-                    this.update_todo(todo)?;
-                    Ok(vec![Content::text("".to_string())])
-                }
-                _ => Err(ToolError::NotFound(tool_name)),
+            if !this.is_allowed(&tool_name) {
+                return Err(ToolError::NotFound(tool_name));
             }
+            let started = Instant::now();
+            let result = if MUTATING_TOOLS.contains(&tool_name.as_str()) {
+                let _guard = this.lock_store();
+                this.dispatch(&tool_name, &arguments)
+            } else {
+                this.dispatch(&tool_name, &arguments)
+            };
+            this.record_call(&tool_name, started.elapsed());
+            if let Ok(content) = &result {
+                this.notify_webhook(&tool_name, &arguments, content);
+            }
+            result
         })
     }
 
-    fn list_resources(&self) -> Vec<mcp_core::resource::Resource> {
-        vec![]
+    fn list_resources(&self) -> Vec<Resource> {
+        let lists = self
+            .get_lists()
+            .unwrap_or_else(|_| vec![DEFAULT_LIST.to_string()]);
+        let last_modified = self.last_modified();
+        lists
+            .into_iter()
+            .filter_map(|list| {
+                let uri = if list == self.default_list {
+                    LIST_URI.to_string()
+                } else {
+                    format!("{}/{}", LIST_URI, list)
+                };
+                let mut resource = Resource::new(uri, Some("text".to_string()), Some(list)).ok()?;
+                if let Some(last_modified) = last_modified {
+                    resource.annotations = Some(Annotations::for_resource(0.0, last_modified));
+                }
+                Some(resource)
+            })
+            .collect()
     }
 
     fn read_resource(
         &self,
         uri: &str,
     ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        let this = self.clone();
         let uri = uri.to_string();
-        Box::pin(async move { Err(ResourceError::NotFound(uri)) })
+
+        Box::pin(async move {
+            let list = if uri == LIST_URI {
+                this.default_list.clone()
+            } else {
+                match uri.strip_prefix(&format!("{}/", LIST_URI)) {
+                    Some(name) if !name.is_empty() => name.to_string(),
+                    _ => return Err(ResourceError::NotFound(uri)),
+                }
+            };
+            let todos = this
+                .get_todos(&list)
+                .map_err(|e| ResourceError::ExecutionError(e.to_string()))?;
+            serde_json::to_string(&todos).map_err(|e| ResourceError::ExecutionError(e.to_string()))
+        })
     }
 
     fn list_prompts(&self) -> Vec<Prompt> {
@@ -263,11 +8267,74 @@ impl mcp_server::Router for TodoRouter {
     }
 }
 
-pub async fn start_server(app: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let router = RouterService(TodoRouter::new(app));
+/// Runs an MCP tool from the UI, going through the same `call_tool` path as
+/// requests over stdio.
+#[tauri::command]
+pub async fn invoke_mcp_tool(
+    router: tauri::State<'_, TodoRouter>,
+    name: String,
+    args: Value,
+) -> Result<Vec<Content>, String> {
+    mcp_server::Router::call_tool(router.inner(), &name, args)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Backs up the store every `interval`, starting one interval after launch.
+/// Failures are logged and retried at the next tick.
+async fn run_backups<R: Runtime>(router: TodoRouter<R>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = router.backup_store() {
+            eprintln!("tauri-todo-mcp: backup failed: {}", e);
+        }
+    }
+}
+
+/// A reminder occurrence that has been announced: the list, the todo id and
+/// when the reminder went off.
+type AnnouncedReminder = (String, u64, DateTime<FixedOffset>);
+
+/// Emits `REMINDER_EVENT` and logs once for each reminder occurrence that
+/// comes due, checking every `REMINDER_CHECK_INTERVAL`. A todo is announced
+/// again when it was dismissed or completed and then came due anew, or when
+/// its schedule reaches its next occurrence.
+pub async fn run_reminder_checks<R: Runtime>(router: TodoRouter<R>) {
+    let mut announced = HashSet::new();
+    let mut ticker = tokio::time::interval(REMINDER_CHECK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        announced = router.announce_reminders(Utc::now(), &announced);
+    }
+}
+
+pub async fn start_server<R: Runtime>(app: AppHandle<R>) -> Result<(), Box<dyn std::error::Error>> {
+    let router = TodoRouter::new(app)?;
+    router.check_store()?;
+    router.migrate_store()?;
+    if let Some(interval) = router.backup_interval {
+        tauri::async_runtime::spawn(run_backups(router.clone(), interval));
+    }
+    tauri::async_runtime::spawn(run_reminder_checks(router.clone()));
+    serve(router, stdin(), stdout()).await
+}
 
-    let server = Server::new(router);
-    let transport = ByteTransport::new(stdin(), stdout());
+/// Serves `router` over any byte stream pair. `start_server` uses stdio; an
+/// in-memory pipe such as `tokio::io::duplex` works just as well.
+pub async fn serve<R, I, O>(
+    router: TodoRouter<R>,
+    reader: I,
+    writer: O,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    R: Runtime,
+    I: AsyncRead + Unpin + Send + 'static,
+    O: AsyncWrite + Unpin + Send + 'static,
+{
+    let server = Server::new(RouterService(router));
+    let transport = ByteTransport::new(reader, writer);
 
     Ok(server.run(transport).await?)
 }
@@ -0,0 +1,311 @@
+//! Persistence for todo lists. `store.json` is shared with the desktop window
+//! and is the default; the SQLite backend avoids rewriting every list on each
+//! save once lists grow large. Tombstones and snapshots always stay in
+//! `store.json`.
+
+use std::{path::PathBuf, sync::Mutex};
+
+use chrono::Utc;
+use mcp_core::ToolError;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Transaction};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use super::{
+    list_key, parse_date, persist_error, reload_store, save_store, store_file_path, Todo,
+    DEFAULT_LIST, LIST_KEY_PREFIX, STORE_PATH,
+};
+
+pub(super) trait TodoStorage: Send + Sync {
+    /// Names of the lists that have been saved at least once. The default
+    /// list may or may not be included.
+    fn lists(&self) -> Result<Vec<String>, ToolError>;
+    fn has_list(&self, list: &str) -> Result<bool, ToolError>;
+    fn load(&self, list: &str) -> Result<Vec<Todo>, ToolError>;
+    fn save(&self, list: &str, todos: &[Todo]) -> Result<(), ToolError>;
+    /// Forgets `list` entirely, so it no longer appears in `lists`.
+    fn remove(&self, list: &str) -> Result<(), ToolError>;
+    /// The file whose modification time is the last time any list changed.
+    fn path(&self) -> Result<PathBuf, ToolError>;
+    /// The `TODO_STORAGE` value that selects this backend.
+    fn kind(&self) -> &'static str;
+}
+
+pub(super) struct JsonStorage<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> JsonStorage<R> {
+    pub(super) fn new(app: AppHandle<R>) -> Self {
+        Self { app }
+    }
+}
+
+impl<R: Runtime> TodoStorage for JsonStorage<R> {
+    fn lists(&self) -> Result<Vec<String>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        Ok(store
+            .keys()
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix(LIST_KEY_PREFIX)
+                    .map(|name| name.to_string())
+            })
+            .collect())
+    }
+
+    fn has_list(&self, list: &str) -> Result<bool, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        Ok(store.has(list_key(list)))
+    }
+
+    fn load(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        reload_store(&store)?;
+        let todos = store
+            .get(list_key(list))
+            .and_then(|value| serde_json::from_value::<Vec<Todo>>(value).ok())
+            .unwrap_or_default();
+        Ok(todos)
+    }
+
+    fn save(&self, list: &str, todos: &[Todo]) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        store.set(
+            list_key(list),
+            serde_json::to_value(todos).map_err(|e| ToolError::ExecutionError(e.to_string()))?,
+        );
+        save_store(&store)
+    }
+
+    fn remove(&self, list: &str) -> Result<(), ToolError> {
+        let store = self
+            .app
+            .store(STORE_PATH)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        if store.delete(list_key(list)) {
+            save_store(&store)?;
+        }
+        Ok(())
+    }
+
+    fn path(&self) -> Result<PathBuf, ToolError> {
+        store_file_path(&self.app)
+    }
+
+    fn kind(&self) -> &'static str {
+        "json"
+    }
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS lists (name TEXT PRIMARY KEY);
+    CREATE TABLE IF NOT EXISTS todos (
+        list TEXT NOT NULL REFERENCES lists (name),
+        position INTEGER NOT NULL,
+        id INTEGER NOT NULL,
+        done INTEGER NOT NULL,
+        due_at INTEGER,
+        data TEXT NOT NULL,
+        PRIMARY KEY (list, position)
+    );
+    CREATE INDEX IF NOT EXISTS todos_by_done ON todos (list, done);
+    CREATE INDEX IF NOT EXISTS todos_by_due ON todos (list, due_at);
+    CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+";
+
+/// Set in `meta` once the lists from `store.json` have been copied over.
+const MIGRATED_KEY: &str = "migrated_from_json";
+
+/// One row per todo, with the full todo as JSON in `data`. `done` and
+/// `due_at` (UTC milliseconds) are duplicated into indexed columns.
+pub(super) struct SqliteStorage {
+    path: PathBuf,
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    /// Opens or creates the database at `path`. The first time, every list in
+    /// `store.json` is copied into it; `store.json` itself is left untouched.
+    pub(super) fn open<R: Runtime>(path: PathBuf, json: &JsonStorage<R>) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("could not create {}: {}", dir.display(), e))?;
+        }
+        let connection = Connection::open(&path)
+            .and_then(|connection| connection.execute_batch(SCHEMA).map(|_| connection))
+            .map_err(|e| {
+                format!(
+                    "could not open the SQLite store at {}: {}",
+                    path.display(),
+                    e
+                )
+            })?;
+        let storage = Self {
+            path,
+            connection: Mutex::new(connection),
+        };
+        storage.migrate_from(json).map_err(|e| {
+            format!(
+                "could not migrate {} into {}: {}",
+                STORE_PATH,
+                storage.path.display(),
+                e
+            )
+        })?;
+        Ok(storage)
+    }
+
+    fn migrate_from<R: Runtime>(&self, json: &JsonStorage<R>) -> Result<(), ToolError> {
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(sql_error)?;
+        let migrated: Option<String> = transaction
+            .query_row(
+                "SELECT value FROM meta WHERE key = ?1",
+                [MIGRATED_KEY],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?;
+        if migrated.is_some() {
+            return Ok(());
+        }
+        let mut lists = json.lists()?;
+        lists.push(DEFAULT_LIST.to_string());
+        for list in lists {
+            if json.has_list(&list)? {
+                write_list(&transaction, &list, &json.load(&list)?).map_err(sql_error)?;
+            }
+        }
+        transaction
+            .execute(
+                "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+                params![MIGRATED_KEY, Utc::now().to_rfc3339()],
+            )
+            .map_err(sql_error)?;
+        transaction.commit().map_err(sql_error)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn sql_error(e: rusqlite::Error) -> ToolError {
+    ToolError::ExecutionError(e.to_string())
+}
+
+/// Like `sql_error`, for writes: the transaction was rolled back, so nothing
+/// was saved.
+fn sql_persist_error(e: rusqlite::Error) -> ToolError {
+    let reason = match e.sqlite_error_code() {
+        Some(ErrorCode::ReadOnly | ErrorCode::PermissionDenied) => Some("the store is read-only"),
+        Some(ErrorCode::DiskFull) => Some("the disk is full"),
+        _ => None,
+    };
+    persist_error(reason, e)
+}
+
+fn write_list(transaction: &Transaction, list: &str, todos: &[Todo]) -> rusqlite::Result<()> {
+    transaction.execute("INSERT OR IGNORE INTO lists (name) VALUES (?1)", [list])?;
+    transaction.execute("DELETE FROM todos WHERE list = ?1", [list])?;
+    let mut insert = transaction.prepare_cached(
+        "INSERT INTO todos (list, position, id, done, due_at, data) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for (position, todo) in todos.iter().enumerate() {
+        let data = serde_json::to_string(todo)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let due_at = todo
+            .due_date
+            .as_deref()
+            .and_then(parse_date)
+            .map(|due| due.timestamp_millis());
+        insert.execute(params![
+            list,
+            position as i64,
+            todo.id as i64,
+            todo.done,
+            due_at,
+            data
+        ])?;
+    }
+    Ok(())
+}
+
+impl TodoStorage for SqliteStorage {
+    fn lists(&self) -> Result<Vec<String>, ToolError> {
+        let connection = self.lock();
+        let mut statement = connection
+            .prepare_cached("SELECT name FROM lists")
+            .map_err(sql_error)?;
+        let lists = statement
+            .query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+            .map_err(sql_error)?;
+        Ok(lists)
+    }
+
+    fn has_list(&self, list: &str) -> Result<bool, ToolError> {
+        self.lock()
+            .query_row("SELECT 1 FROM lists WHERE name = ?1", [list], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+            .map_err(sql_error)
+    }
+
+    fn load(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        let connection = self.lock();
+        let mut statement = connection
+            .prepare_cached("SELECT data FROM todos WHERE list = ?1 ORDER BY position")
+            .map_err(sql_error)?;
+        let rows = statement
+            .query_map([list], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<String>>>())
+            .map_err(sql_error)?;
+        rows.iter()
+            .map(|data| {
+                serde_json::from_str(data).map_err(|e| ToolError::ExecutionError(e.to_string()))
+            })
+            .collect()
+    }
+
+    fn save(&self, list: &str, todos: &[Todo]) -> Result<(), ToolError> {
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(sql_persist_error)?;
+        write_list(&transaction, list, todos).map_err(sql_persist_error)?;
+        transaction.commit().map_err(sql_persist_error)
+    }
+
+    fn remove(&self, list: &str) -> Result<(), ToolError> {
+        let mut connection = self.lock();
+        let transaction = connection.transaction().map_err(sql_persist_error)?;
+        transaction
+            .execute("DELETE FROM todos WHERE list = ?1", [list])
+            .and_then(|_| transaction.execute("DELETE FROM lists WHERE name = ?1", [list]))
+            .map_err(sql_persist_error)?;
+        transaction.commit().map_err(sql_persist_error)
+    }
+
+    fn path(&self) -> Result<PathBuf, ToolError> {
+        Ok(self.path.clone())
+    }
+
+    fn kind(&self) -> &'static str {
+        "sqlite"
+    }
+}
@@ -0,0 +1,2581 @@
+use std::path::Path;
+
+use serde_json::json;
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use super::*;
+
+/// Builds an app whose data directory, and so `store.json`, is `dir`.
+fn app_in(dir: &Path) -> tauri::App<MockRuntime> {
+    let mut context = mock_context(noop_assets());
+    // `app_data_dir` joins the identifier onto the platform data directory,
+    // and joining an absolute path replaces it.
+    context.config_mut().identifier = dir.to_string_lossy().into_owned();
+    mock_builder()
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .build(context)
+        .expect("failed to build the mock app")
+}
+
+/// A router started the way `start_server` starts one, over the store in
+/// `dir`. An empty `store.json` is written first if there is none, like the
+/// one the app leaves behind after its first run.
+fn router_in(dir: &Path) -> TodoRouter<MockRuntime> {
+    let path = dir.join(STORE_PATH);
+    if !path.exists() {
+        std::fs::write(&path, "{}").unwrap();
+    }
+    let router = TodoRouter::new(app_in(dir).handle().clone()).unwrap();
+    router.check_store().unwrap();
+    router.migrate_store().unwrap();
+    router
+}
+
+/// A router over a store of its own, removed when the returned directory is
+/// dropped.
+fn router() -> (tempfile::TempDir, TodoRouter<MockRuntime>) {
+    let dir = tempfile::tempdir().unwrap();
+    let router = router_in(dir.path());
+    (dir, router)
+}
+
+/// Calls `name` the way the stdio server does and returns the text of its
+/// first content block.
+fn call(
+    router: &TodoRouter<MockRuntime>,
+    name: &str,
+    arguments: Value,
+) -> Result<String, ToolError> {
+    let content =
+        tauri::async_runtime::block_on(mcp_server::Router::call_tool(router, name, arguments))?;
+    Ok(content
+        .first()
+        .and_then(Content::as_text)
+        .unwrap_or_default()
+        .to_string())
+}
+
+fn call_json(router: &TodoRouter<MockRuntime>, name: &str, arguments: Value) -> Value {
+    let text = call(router, name, arguments).unwrap();
+    serde_json::from_str(&text).unwrap_or_else(|e| panic!("{} returned {:?}: {}", name, text, e))
+}
+
+fn add(router: &TodoRouter<MockRuntime>, text: &str) -> u64 {
+    call_json(router, "add_todo", json!({ "text": text }))["id"]
+        .as_u64()
+        .unwrap()
+}
+
+/// Sends each request to `serve` over an in-memory pipe and returns the
+/// response to each, in order.
+fn rpc(router: TodoRouter<MockRuntime>, requests: Vec<Value>) -> Vec<Value> {
+    tauri::async_runtime::block_on(async move {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (server_reader, server_writer) = tokio::io::split(server);
+        let server = tauri::async_runtime::spawn(async move {
+            serve(router, server_reader, server_writer)
+                .await
+                .map_err(|e| e.to_string())
+        });
+        let (client_reader, mut client_writer) = tokio::io::split(client);
+        let mut lines = BufReader::new(client_reader).lines();
+        let mut responses = Vec::new();
+        for request in requests {
+            client_writer
+                .write_all(format!("{}\n", request).as_bytes())
+                .await
+                .unwrap();
+            let line = lines.next_line().await.unwrap().expect("server hung up");
+            responses.push(serde_json::from_str(&line).unwrap());
+        }
+        drop(client_writer);
+        drop(lines);
+        server.await.unwrap().unwrap();
+        responses
+    })
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn tool_call(id: u64, name: &str, arguments: Value) -> Value {
+    request(
+        id,
+        "tools/call",
+        json!({ "name": name, "arguments": arguments }),
+    )
+}
+
+/// The text of a `tools/call` result, failing the test if the call errored.
+fn result_text(response: &Value) -> &str {
+    assert_eq!(response["result"]["isError"], Value::Null, "{}", response);
+    response["result"]["content"][0]["text"].as_str().unwrap()
+}
+
+#[test]
+fn initialize_reports_the_server() {
+    let (_dir, router) = router();
+    let responses = rpc(
+        router,
+        vec![request(
+            1,
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "test", "version": "1" }
+            }),
+        )],
+    );
+    let response = &responses[0];
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["error"], Value::Null);
+    assert_eq!(response["result"]["serverInfo"]["name"], "todo");
+    assert!(response["result"]["capabilities"]["tools"].is_object());
+    assert!(response["result"]["instructions"].is_string());
+}
+
+#[test]
+fn tools_call_round_trips_a_todo() {
+    let (_dir, router) = router();
+    let responses = rpc(
+        router,
+        vec![
+            tool_call(1, "add_todo", json!({ "text": "Buy milk" })),
+            tool_call(2, "get_todos", json!({})),
+            tool_call(3, "no_such_tool", json!({})),
+        ],
+    );
+    let added: Value = serde_json::from_str(result_text(&responses[0])).unwrap();
+    assert_eq!(added["text"], "Buy milk");
+    assert_eq!(added["done"], false);
+
+    assert_eq!(responses[1]["id"], 2);
+    let todos: Value = serde_json::from_str(result_text(&responses[1])).unwrap();
+    assert_eq!(todos[0]["id"], added["id"]);
+
+    assert_eq!(responses[2]["result"]["isError"], true);
+    assert!(responses[2]["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("no_such_tool"));
+}
+
+/// Every listed tool answers `tools/call` with a result, even when called
+/// without the arguments it needs; failures come back as `isError` results
+/// rather than as protocol errors or a dropped connection.
+#[test]
+fn every_tool_answers_tools_call() {
+    let (_dir, router) = router();
+    add(&router, "Existing todo");
+    let tools = mcp_server::Router::list_tools(&router);
+    let requests = tools
+        .iter()
+        .enumerate()
+        .map(|(id, tool)| tool_call(id as u64, &tool.name, json!({})))
+        .collect();
+    let responses = rpc(router, requests);
+    assert_eq!(responses.len(), tools.len());
+    for ((id, tool), response) in tools.iter().enumerate().zip(&responses) {
+        assert_eq!(response["id"], id as u64, "{}", tool.name);
+        assert_eq!(
+            response["error"],
+            Value::Null,
+            "{}: {}",
+            tool.name,
+            response
+        );
+        let content = response["result"]["content"]
+            .as_array()
+            .unwrap_or_else(|| panic!("{}: {}", tool.name, response));
+        assert!(
+            content.iter().all(|block| block["type"] == "text"),
+            "{}: {}",
+            tool.name,
+            response
+        );
+    }
+}
+
+/// Each tool called once over the pipe with arguments that succeed, checking
+/// what its result says. The calls run in order against one store, so each
+/// sees what the earlier ones did.
+#[test]
+fn every_tool_answers_with_its_result() {
+    let (_dir, router) = router();
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    let report = call_json(
+        &router,
+        "add_todo",
+        json!({
+            "text": "Write report",
+            "tags": ["work"],
+            "priority": "high",
+            "due_date": "2000-01-01T09:00:00Z"
+        }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    let milk = add(&router, "Buy milk");
+    let old = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Old task", "done": true }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    let bundle = json!({
+        "format": BUNDLE_FORMAT,
+        "version": BUNDLE_VERSION,
+        "server": "todo",
+        "server_version": "0",
+        "generated_at": "2024-01-01T00:00:00Z",
+        "list": "elsewhere",
+        "todos": [{ "id": 1, "text": "Imported", "done": false }]
+    });
+    let calls = [
+        ("get_todos", json!({})),
+        ("open_todos", json!({})),
+        ("done_todos", json!({})),
+        (
+            "set_due_date",
+            json!({ "id": milk, "due_date": "2099-01-05T09:00:00Z" }),
+        ),
+        ("set_priority", json!({ "id": milk, "priority": "low" })),
+        ("revert_todo", json!({ "id": milk })),
+        ("agenda", json!({ "date": "2099-01-05" })),
+        ("add_subtask", json!({ "id": report, "text": "Outline" })),
+        (
+            "set_subtask_done",
+            json!({ "id": report, "index": 0, "done": true }),
+        ),
+        (
+            "toggle_all_subtasks",
+            json!({ "id": report, "done": false }),
+        ),
+        ("set_estimate", json!({ "id": report, "minutes": 60 })),
+        ("log_time", json!({ "id": report, "minutes": 15 })),
+        ("workload", json!({})),
+        (
+            "set_notes",
+            json!({ "id": report, "notes": "Spec: https://example.com/spec" }),
+        ),
+        ("export_ics", json!({})),
+        (
+            "list_todos_changed_since",
+            json!({ "since": "2000-01-01T00:00:00Z" }),
+        ),
+        (
+            "set_tags",
+            json!({ "id": milk, "tags": ["home", "errand"] }),
+        ),
+        (
+            "retag",
+            json!({ "from_tag": "errand", "to_tag": "shopping" }),
+        ),
+        ("remove_tag_everywhere", json!({ "tag": "shopping" })),
+        ("create_snapshot", json!({ "name": "before" })),
+        ("list_snapshots", json!({})),
+        ("sort_todos", json!({ "by": "text" })),
+        ("stale_todos", json!({ "older_than_days": 1 })),
+        ("metrics", json!({})),
+        ("reset_metrics", json!({})),
+        (
+            "split_todo",
+            json!({ "id": milk, "texts": ["Buy oat milk", "Buy bread"] }),
+        ),
+        ("streak", json!({ "timezone": "UTC" })),
+        ("compact", json!({})),
+        (
+            "add_attachment",
+            json!({ "id": report, "attachment": "report.pdf" }),
+        ),
+        (
+            "remove_attachment",
+            json!({ "id": report, "attachment": "report.pdf" }),
+        ),
+        ("query_todos", json!({ "filter": { "tags": ["work"] } })),
+        (
+            "add_reminder",
+            json!({ "id": report, "at": "2000-01-01T08:00:00Z" }),
+        ),
+        ("due_reminders", json!({})),
+        (
+            "remove_reminder",
+            json!({ "id": report, "at": "2000-01-01T08:00:00Z" }),
+        ),
+        ("dismiss_reminders", json!({ "id": report })),
+        ("create_list", json!({ "name": "errands" })),
+        ("add_todo", json!({ "text": "Call  mom", "tags": ["home"] })),
+        ("normalize_todos", json!({ "collapse_spaces": true })),
+        (
+            "set_dependencies",
+            json!({ "id": report, "depends_on": [old] }),
+        ),
+        ("plan", json!({})),
+        ("render_markdown", json!({})),
+        ("summarize", json!({})),
+        ("store_stats", json!({})),
+        ("list_tags", json!({})),
+        (
+            "complete_matching",
+            json!({ "filter": { "text_contains": "MOM" } }),
+        ),
+        ("recent_activity", json!({ "count": 2 })),
+        ("bump_todo", json!({ "id": report })),
+        ("auto_prioritize", json!({})),
+        (
+            "due_on_weekday",
+            json!({ "weekday": "saturday", "timezone": "UTC" }),
+        ),
+        ("needs_triage", json!({})),
+        ("get_list_settings", json!({})),
+        (
+            "set_list_settings",
+            json!({ "default_due_offset": "+3 days" }),
+        ),
+        ("export_table", json!({ "columns": ["id", "text"] })),
+        ("store_info", json!({})),
+        ("todo_progress", json!({ "id": report })),
+        ("set_focus", json!({ "id": report })),
+        ("get_focus", json!({})),
+        ("clear_focus", json!({})),
+        ("created_histogram", json!({ "timezone": "UTC" })),
+        ("upsert_todo", json!({ "id": 4242, "text": "Synced" })),
+        ("oldest_open", json!({ "limit": 1 })),
+        (
+            "check_due_date",
+            json!({ "due_date": "2000-01-01T00:00:00Z" }),
+        ),
+        ("list_ids", json!({ "filter": { "done": true } })),
+        ("random_todo", json!({ "tags": ["work"], "seed": 1 })),
+        ("promote_subtask", json!({ "id": report, "index": 0 })),
+        ("related_todos", json!({ "id": report })),
+        ("week_view", json!({ "timezone": "UTC" })),
+        (
+            "prioritize_matching",
+            json!({ "filter": { "tags": ["work"] }, "priority": "medium" }),
+        ),
+        ("set_assignee", json!({ "id": report, "assignee": " sam " })),
+        ("workload_by_assignee", json!({})),
+        ("most_overdue", json!({})),
+        (
+            "move_todos",
+            json!({ "ids": [4242], "from": "default", "to": "work" }),
+        ),
+        (
+            "overloaded_days",
+            json!({ "max_per_day": 0, "timezone": "UTC" }),
+        ),
+        (
+            "reopen_recent",
+            json!({ "within_hours": 1, "dry_run": true }),
+        ),
+        ("schema_status", json!({})),
+        ("export_snapshot", json!({ "list": "work" })),
+        (
+            "import_todos",
+            json!({ "list": "errands", "bundle": bundle }),
+        ),
+        ("find_by_id_prefix", json!({ "prefix": report.to_string() })),
+        ("forecast", json!({ "window_days": 7, "timezone": "UTC" })),
+        ("set_list", json!({ "id": old, "to": "errands" })),
+        (
+            "tag_matching",
+            json!({ "filter": { "tags": ["work"] }, "tag": "q1" }),
+        ),
+        ("diff_snapshots", json!({ "from": "before" })),
+        ("tag_cooccurrence", json!({})),
+        (
+            "set_status",
+            json!({ "id": report, "status": "in_progress" }),
+        ),
+        ("wip_status", json!({ "limit": 1 })),
+        (
+            "import_outline",
+            json!({ "list": "errands", "text": "- Pack\n  - Socks" }),
+        ),
+        ("extract_links", json!({})),
+        ("done_today", json!({ "timezone": "UTC" })),
+        (
+            "set_reminder_schedule",
+            json!({ "id": report, "every": "1 week" }),
+        ),
+        (
+            "reschedule_overdue",
+            json!({ "to": "2100-01-01T00:00:00Z" }),
+        ),
+        ("restore_snapshot", json!({ "name": "before" })),
+        ("archive_list", json!({ "name": "errands" })),
+        ("get_lists", json!({ "include_archived": true })),
+        ("restore_list", json!({ "name": "errands" })),
+        (
+            "update_todo",
+            json!({ "id": report, "text": "Write the report", "done": true }),
+        ),
+        ("remove_todo", json!({ "id": report })),
+    ];
+    let tools: BTreeSet<String> = mcp_server::Router::list_tools(&router)
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    let called: BTreeSet<String> = calls.iter().map(|(tool, _)| tool.to_string()).collect();
+    assert_eq!(called, tools);
+    assert_eq!(called.len(), calls.len(), "a tool is called twice");
+
+    let requests = calls
+        .iter()
+        .enumerate()
+        .map(|(id, (tool, arguments))| tool_call(id as u64, tool, arguments.clone()))
+        .collect();
+    let responses = rpc(router.clone(), requests);
+    // Results that aren't JSON, like Markdown, are kept as strings.
+    let results: HashMap<&str, Value> = calls
+        .iter()
+        .zip(&responses)
+        .map(|((tool, _), response)| {
+            let text = result_text(response);
+            let result = serde_json::from_str(text).unwrap_or_else(|_| json!(text));
+            (*tool, result)
+        })
+        .collect();
+    let result = |tool: &str| &results[tool];
+    let text = |tool: &str| result(tool).as_str().unwrap();
+    let texts = |todos: &Value| -> Vec<String> {
+        todos
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|todo| todo["text"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        texts(result("get_todos")),
+        ["Write report", "Buy milk", "Old task"]
+    );
+    assert_eq!(texts(result("open_todos")), ["Write report", "Buy milk"]);
+    assert_eq!(texts(result("done_todos")), ["Old task"]);
+    assert_eq!(
+        result("set_due_date")["due_date"],
+        "2099-01-05T09:00:00+00:00"
+    );
+    assert_eq!(result("set_priority")["priority"], "low");
+    assert_eq!(result("revert_todo")["priority"], Value::Null);
+    assert_eq!(
+        text("agenda"),
+        "Agenda for 2099-01-05\n\n\
+         Overdue:\n\
+         1. Write report (id: {report}, due 2000-01-01T09:00:00+00:00)\n\n\
+         Due today:\n\
+         2. Buy milk (id: {milk}, due 2099-01-05T09:00:00+00:00)"
+            .replace("{report}", &report.to_string())
+            .replace("{milk}", &milk.to_string())
+    );
+    assert_eq!(
+        result("add_subtask")["subtasks"],
+        json!([{ "text": "Outline", "done": false }])
+    );
+    assert_eq!(result("set_subtask_done")["subtasks"][0]["done"], true);
+    assert_eq!(result("toggle_all_subtasks")["subtasks"][0]["done"], false);
+    assert_eq!(result("set_estimate")["estimate_minutes"], 60);
+    assert_eq!(result("log_time")["spent_minutes"], 15);
+    assert_eq!(
+        result("workload"),
+        &json!({
+            "open_todos": 2,
+            "unestimated_todos": 1,
+            "estimated_minutes": 60,
+            "spent_minutes": 15,
+            "remaining_minutes": 45
+        })
+    );
+    assert_eq!(
+        result("set_notes")["notes"],
+        "Spec: https://example.com/spec"
+    );
+    let ics = text("export_ics");
+    assert!(
+        ics.contains("SUMMARY:Write report\r\nDUE:20000101T090000Z"),
+        "{}",
+        ics
+    );
+    assert_eq!(
+        result("list_todos_changed_since")["todos"]
+            .as_array()
+            .unwrap()
+            .len(),
+        3
+    );
+    assert_eq!(result("list_todos_changed_since")["deleted"], json!([]));
+    assert_eq!(result("set_tags")["tags"], json!(["home", "errand"]));
+    assert_eq!(result("retag"), &json!({ "affected": 1 }));
+    assert_eq!(result("remove_tag_everywhere"), &json!({ "affected": 1 }));
+    assert_eq!(result("create_snapshot")["todos"], 3);
+    assert_eq!(result("list_snapshots")[0]["name"], "before");
+    assert_eq!(
+        texts(result("sort_todos")),
+        ["Buy milk", "Old task", "Write report"]
+    );
+    assert_eq!(result("stale_todos"), &json!([]));
+    // The setup calls count too: they go through `call_tool` as well.
+    assert_eq!(result("metrics")["add_todo"]["count"], 3);
+    assert_eq!(result("metrics")["get_todos"]["count"], 1);
+    assert_eq!(text("reset_metrics"), "");
+    assert_eq!(texts(result("split_todo")), ["Buy oat milk", "Buy bread"]);
+    assert_eq!(result("split_todo")[1]["tags"], json!(["home"]));
+    assert_eq!(result("streak"), &json!({ "current": 1, "longest": 1 }));
+    assert_eq!(
+        texts(result("compact")),
+        ["Write report", "Buy oat milk", "Buy bread", "Old task"]
+    );
+    assert_eq!(
+        result("add_attachment")["attachments"],
+        json!(["report.pdf"])
+    );
+    assert_eq!(result("remove_attachment")["attachments"], Value::Null);
+    assert_eq!(texts(result("query_todos")), ["Write report"]);
+    assert_eq!(
+        result("add_reminder")["reminders"],
+        json!(["2000-01-01T08:00:00+00:00"])
+    );
+    assert_eq!(result("due_reminders")[0]["id"], report);
+    assert_eq!(result("remove_reminder")["reminders"], Value::Null);
+    assert!(result("dismiss_reminders")["reminders_dismissed_at"].is_string());
+    assert_eq!(
+        result("create_list"),
+        &json!(["default", "errands", "work"])
+    );
+    assert_eq!(result("add_todo")["text"], "Call  mom");
+    assert_eq!(result("normalize_todos"), &json!({ "changed": 1 }));
+    assert_eq!(result("set_dependencies")["depends_on"], json!([old]));
+    assert_eq!(
+        texts(result("plan")),
+        ["Write report", "Buy oat milk", "Buy bread", "Call mom"]
+    );
+    assert_eq!(
+        text("render_markdown"),
+        "# default\n\n\
+         - [ ] Write report\n  - [ ] Outline\n\
+         - [ ] Buy oat milk\n- [ ] Buy bread\n- [x] Old task\n- [ ] Call mom\n"
+    );
+    assert!(
+        text("summarize").starts_with("4 open, 1 done, 1 overdue.\n1. Write report"),
+        "{}",
+        text("summarize")
+    );
+    assert_eq!(result("store_stats")["todos"], 5);
+    assert_eq!(result("list_tags"), &json!({ "home": 3, "work": 1 }));
+    assert_eq!(result("complete_matching")["count"], 1);
+    assert_eq!(texts(&result("complete_matching")["todos"]), ["Call mom"]);
+    assert_eq!(result("recent_activity").as_array().unwrap().len(), 2);
+    assert_eq!(result("recent_activity")[0]["todo"]["text"], "Call mom");
+    assert_eq!(result("bump_todo")["id"], report);
+    assert_eq!(result("auto_prioritize"), &json!({ "changed": 3 }));
+    // 2000-01-01 was a Saturday.
+    assert_eq!(texts(result("due_on_weekday")), ["Write report"]);
+    assert_eq!(texts(result("needs_triage")), ["Buy oat milk", "Buy bread"]);
+    assert_eq!(result("get_list_settings"), &json!({}));
+    assert_eq!(
+        result("set_list_settings"),
+        &json!({ "default_due_offset": "+3 days" })
+    );
+    assert!(
+        text("export_table").starts_with(&format!(
+            "| ID | Text |\n| --- | --- |\n| {} | Write report |\n",
+            report
+        )),
+        "{}",
+        text("export_table")
+    );
+    assert_eq!(result("store_info")["storage"], "json");
+    assert_eq!(result("store_info")["exists"], true);
+    assert_eq!(
+        result("todo_progress"),
+        &json!({ "done": 0, "total": 1, "percent": 0 })
+    );
+    assert_eq!(result("set_focus")["id"], report);
+    assert_eq!(result("get_focus")["id"], report);
+    assert_eq!(text("clear_focus"), "");
+    let created: u64 = result("created_histogram")
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|count| count.as_u64().unwrap())
+        .sum();
+    assert_eq!(created, 5);
+    assert_eq!(result("upsert_todo")["created"], true);
+    assert_eq!(result("upsert_todo")["todo"]["id"], 4242);
+    // The `+3 days` default set above applies to upserted todos too.
+    assert!(result("upsert_todo")["todo"]["due_date"].is_string());
+    assert_eq!(texts(result("oldest_open")), ["Write report"]);
+    assert_eq!(result("check_due_date")["status"], "past");
+    assert_eq!(result("list_ids").as_array().unwrap().len(), 2);
+    assert_eq!(result("list_ids")[0], old);
+    assert_eq!(result("random_todo")["id"], report);
+    assert_eq!(result("promote_subtask")["text"], "Outline");
+    assert_eq!(result("promote_subtask")["tags"], json!(["work"]));
+    assert_eq!(texts(result("related_todos")), ["Outline"]);
+    assert_eq!(texts(&result("week_view")["overdue"]), ["Write report"]);
+    assert_eq!(result("prioritize_matching")["count"], 2);
+    assert_eq!(result("set_assignee")["assignee"], "sam");
+    assert_eq!(result("workload_by_assignee")[1]["assignee"], "sam");
+    assert_eq!(result("workload_by_assignee")[1]["remaining_minutes"], 45);
+    assert_eq!(result("most_overdue")["id"], report);
+    assert_eq!(
+        result("move_todos"),
+        &json!({ "moved": [4242], "not_found": [] })
+    );
+    assert_eq!(result("overloaded_days")[0]["date"], "2000-01-01");
+    assert_eq!(result("reopen_recent")["dry_run"], true);
+    assert_eq!(result("reopen_recent")["count"], 2);
+    assert_eq!(result("schema_status")["pending"], false);
+    assert_eq!(result("export_snapshot")["list"], "work");
+    assert_eq!(result("export_snapshot")["todos"][0]["id"], 4242);
+    assert_eq!(texts(result("import_todos")), ["Imported"]);
+    assert_eq!(texts(result("find_by_id_prefix")), ["Write report"]);
+    assert_eq!(result("forecast")["open_todos"], 4);
+    assert_eq!(result("set_list")["text"], "Old task");
+    assert_eq!(result("tag_matching")["count"], 2);
+    assert_eq!(result("diff_snapshots")["to"], "current");
+    assert_eq!(
+        texts(&result("diff_snapshots")["removed"]),
+        ["Buy milk", "Old task"]
+    );
+    assert_eq!(
+        result("tag_cooccurrence"),
+        &json!([{ "tags": ["q1", "work"], "count": 2 }])
+    );
+    assert_eq!(result("set_status")["status"], "in_progress");
+    assert_eq!(result("wip_status")["in_progress"], 1);
+    assert_eq!(result("wip_status")["over_limit"], false);
+    assert_eq!(
+        result("import_outline"),
+        &json!({ "todos": 1, "subtasks": 1 })
+    );
+    assert_eq!(
+        result("extract_links"),
+        &json!({ report.to_string(): ["https://example.com/spec"] })
+    );
+    assert_eq!(texts(result("done_today")), ["Call mom"]);
+    assert_eq!(
+        result("set_reminder_schedule")["reminder_schedule"]["every"],
+        "1 week"
+    );
+    assert_eq!(result("reschedule_overdue"), &json!({ "rescheduled": 1 }));
+    assert_eq!(result("restore_snapshot")["restored"], "before");
+    assert_eq!(
+        result("archive_list"),
+        &json!({ "archived": "errands", "todos": 3 })
+    );
+    assert_eq!(
+        result("get_lists"),
+        &json!({ "lists": ["default", "work"], "archived": ["errands"] })
+    );
+    assert_eq!(
+        result("restore_list"),
+        &json!({ "restored": "errands", "todos": 3 })
+    );
+    assert_eq!(text("update_todo"), "");
+    assert_eq!(text("remove_todo"), "");
+    // The snapshot brought back the list as it was before `split_todo`.
+    assert_eq!(
+        texts(&call_json(&router, "get_todos", json!({}))),
+        ["Buy milk", "Old task"]
+    );
+}
+
+/// Overlapping mutating calls must not lose each other's updates: every
+/// thread's surviving todos and snapshots are still there at the end.
+#[test]
+fn overlapping_mutations_keep_every_update() {
+    const THREADS: usize = 8;
+    const TODOS_PER_THREAD: usize = 10;
+    let (_dir, router) = router();
+    let kept: Vec<u64> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let router = router.clone();
+                scope.spawn(move || {
+                    let mut kept = Vec::new();
+                    for n in 0..TODOS_PER_THREAD {
+                        let id = add(&router, &format!("thread {} todo {}", thread, n));
+                        if n % 2 == 0 {
+                            call(&router, "remove_todo", json!({ "id": id })).unwrap();
+                        } else {
+                            kept.push(id);
+                        }
+                    }
+                    call(
+                        &router,
+                        "create_snapshot",
+                        json!({ "name": format!("thread-{}", thread) }),
+                    )
+                    .unwrap();
+                    kept
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    let todos = call_json(&router, "get_todos", json!({}));
+    let mut ids: Vec<u64> = todos
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|todo| todo["id"].as_u64().unwrap())
+        .collect();
+    let mut expected = kept;
+    ids.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(ids, expected);
+
+    let snapshots = call_json(&router, "list_snapshots", json!({}));
+    assert_eq!(snapshots.as_array().unwrap().len(), THREADS);
+}
+
+/// The app and a separately launched `mcp` process share `store.json` but
+/// not the process-wide write lock. Two apps over one directory, each with a
+/// lock of its own, stand in for them.
+#[test]
+fn separate_processes_keep_every_update() {
+    const TODOS_PER_PROCESS: usize = 20;
+    let dir = tempfile::tempdir().unwrap();
+    let app = router_in(dir.path());
+    let mut mcp = router_in(dir.path());
+    mcp.write_lock = Arc::default();
+    std::thread::scope(|scope| {
+        for (name, router) in [("app", &app), ("mcp", &mcp)] {
+            scope.spawn(move || {
+                for n in 0..TODOS_PER_PROCESS {
+                    add(router, &format!("{} todo {}", name, n));
+                }
+            });
+        }
+    });
+    for router in [&app, &mcp] {
+        let todos = call_json(router, "get_todos", json!({}));
+        assert_eq!(todos.as_array().unwrap().len(), 2 * TODOS_PER_PROCESS);
+    }
+}
+
+#[test]
+fn upsert_rejects_ids_outside_the_safe_range() {
+    let (_dir, router) = router();
+    for id in [json!(0), json!(u64::MAX), json!(MAX_TODO_ID + 1)] {
+        let result = call(&router, "upsert_todo", json!({ "id": id, "text": "x" }));
+        assert_eq!(result, Err(ToolError::InvalidParameters("id".to_string())));
+    }
+    let upserted = call_json(
+        &router,
+        "upsert_todo",
+        json!({ "id": MAX_TODO_ID, "text": "x" }),
+    );
+    assert_eq!(upserted["todo"]["id"], MAX_TODO_ID);
+}
+
+#[test]
+fn new_ids_never_wrap_around() {
+    let now = Utc::now();
+    let last = Todo {
+        id: u64::MAX - 1,
+        ..Default::default()
+    };
+    assert_eq!(next_id(std::slice::from_ref(&last), now).unwrap(), u64::MAX);
+    assert_eq!(
+        next_ids(std::slice::from_ref(&last), now, 1).unwrap(),
+        u64::MAX
+    );
+    assert!(matches!(
+        next_ids(std::slice::from_ref(&last), now, 2),
+        Err(ToolError::ExecutionError(_))
+    ));
+    let full = Todo {
+        id: u64::MAX,
+        ..Default::default()
+    };
+    assert!(matches!(
+        next_id(&[full], now),
+        Err(ToolError::ExecutionError(_))
+    ));
+}
+
+#[test]
+fn stale_todos_rejects_an_unrepresentable_cutoff() {
+    let (_dir, router) = router();
+    let result = call(
+        &router,
+        "stale_todos",
+        json!({ "older_than_days": u32::MAX }),
+    );
+    assert_eq!(
+        result,
+        Err(ToolError::InvalidParameters("older_than_days".to_string()))
+    );
+    assert_eq!(
+        call_json(&router, "stale_todos", json!({ "older_than_days": 36500 })),
+        json!([])
+    );
+}
+
+#[test]
+fn priority_rules_accept_windows_past_the_last_date() {
+    let rules = PriorityRules {
+        high_within_days: u32::MAX,
+        medium_within_days: u32::MAX,
+        old_after_days: u32::MAX,
+    };
+    let now = Utc::now();
+    let due = Todo {
+        due_date: Some("9999-12-31T00:00:00Z".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(rules.priority(&due, now), Priority::High);
+    let undated = Todo {
+        created_at: Some("1970-01-01T00:00:00Z".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(rules.priority(&undated, now), Priority::Low);
+
+    let (_dir, router) = router();
+    add(&router, "Someday");
+    let changed = call_json(
+        &router,
+        "auto_prioritize",
+        json!({
+            "high_within_days": u32::MAX,
+            "medium_within_days": u32::MAX,
+            "old_after_days": u32::MAX
+        }),
+    );
+    assert_eq!(changed["changed"], 1);
+}
+
+#[test]
+fn reopen_recent_rejects_an_unrepresentable_window() {
+    let (_dir, router) = router();
+    let result = call(
+        &router,
+        "reopen_recent",
+        json!({ "within_hours": u32::MAX }),
+    );
+    assert_eq!(
+        result,
+        Err(ToolError::InvalidParameters("within_hours".to_string()))
+    );
+    let id = add(&router, "Done just now");
+    call(
+        &router,
+        "update_todo",
+        json!({ "id": id, "text": "Done just now", "done": true }),
+    )
+    .unwrap();
+    let reopened = call_json(&router, "reopen_recent", json!({ "within_hours": 24 }));
+    assert_eq!(reopened["count"], 1);
+}
+
+#[test]
+fn forecast_rejects_an_unrepresentable_window() {
+    let (_dir, router) = router();
+    add(&router, "Open");
+    let result = call(&router, "forecast", json!({ "window_days": u32::MAX }));
+    assert_eq!(
+        result,
+        Err(ToolError::InvalidParameters("window_days".to_string()))
+    );
+    let forecast = call_json(&router, "forecast", json!({ "window_days": 7 }));
+    assert_eq!(forecast["open_todos"], 1);
+    assert_eq!(forecast["estimated_days"], Value::Null);
+    assert_eq!(
+        forecast["explanation"],
+        "no todos were completed in the last 7 days, so there is no pace to project from"
+    );
+}
+
+#[test]
+fn reminder_schedules_are_validated() {
+    let (_dir, router) = router();
+    let id = add(&router, "Water the plants");
+    for (arguments, field) in [
+        (json!({ "id": id, "every": "fortnightly" }), "every"),
+        (json!({ "id": id, "every": "0 days" }), "every"),
+        (json!({ "id": id, "every": "20000000 weeks" }), "every"),
+        (
+            json!({ "id": id, "every": "1 week", "starts_at": "next monday" }),
+            "starts_at",
+        ),
+    ] {
+        let result = call(&router, "set_reminder_schedule", arguments);
+        assert_eq!(result, Err(ToolError::InvalidParameters(field.to_string())));
+    }
+
+    let todo = call_json(
+        &router,
+        "set_reminder_schedule",
+        json!({ "id": id, "every": " 1 week ", "starts_at": "2024-01-01T09:00:00+09:00" }),
+    );
+    assert_eq!(todo["reminder_schedule"]["every"], "1 week");
+    assert_eq!(
+        todo["reminder_schedule"]["starts_at"],
+        "2024-01-01T09:00:00+09:00"
+    );
+    let todo = call_json(&router, "set_reminder_schedule", json!({ "id": id }));
+    assert_eq!(todo["reminder_schedule"], Value::Null);
+}
+
+/// A repeating reminder is announced once per occurrence, not just once.
+#[test]
+fn each_scheduled_occurrence_is_announced() {
+    let (_dir, router) = router();
+    let id = add(&router, "Stretch");
+    let start = Utc::now() - chrono::Duration::minutes(90);
+    call(
+        &router,
+        "set_reminder_schedule",
+        json!({ "id": id, "every": "1h", "starts_at": start.to_rfc3339() }),
+    )
+    .unwrap();
+    let events = Arc::new(Mutex::new(0));
+    let counter = events.clone();
+    tauri::Listener::listen_any(&router.app, REMINDER_EVENT, move |_| {
+        *counter.lock().unwrap() += 1
+    });
+
+    let now = Utc::now();
+    let announced = router.announce_reminders(now, &HashSet::new());
+    assert_eq!(announced.len(), 1);
+    let announced = router.announce_reminders(now, &announced);
+    assert_eq!(*events.lock().unwrap(), 1);
+
+    let later = now + chrono::Duration::hours(1);
+    let announced = router.announce_reminders(later, &announced);
+    assert_eq!(*events.lock().unwrap(), 2);
+    assert_eq!(announced.len(), 1);
+
+    call(&router, "dismiss_reminders", json!({ "id": id })).unwrap();
+    assert!(router.announce_reminders(now, &announced).is_empty());
+}
+
+/// Argument errors name the offending field and say what was expected.
+#[test]
+fn argument_errors_name_the_field() {
+    let (_dir, router) = router();
+    let id = add(&router, "Pack");
+    for (tool, arguments, error) in [
+        ("add_todo", json!({}), "missing field `text`"),
+        (
+            "add_todo",
+            json!({ "text": "x", "done": "yes" }),
+            "done: invalid type: string \"yes\", expected a boolean",
+        ),
+        (
+            "set_due_date",
+            json!({ "id": id, "due_date": "soon" }),
+            "due_date: invalid value: string \"soon\", expected an RFC3339 date",
+        ),
+        (
+            "log_time",
+            json!({ "id": id, "minutes": -5 }),
+            "minutes: invalid value: integer `-5`, expected u32",
+        ),
+        (
+            "split_todo",
+            json!({ "id": id, "texts": [] }),
+            "texts: invalid length 0, expected at least one text",
+        ),
+        (
+            "set_tags",
+            json!({ "id": id, "tags": ["ok", " "] }),
+            "tags: invalid length 0, expected a non-blank string",
+        ),
+        ("add_reminder", json!({ "id": id }), "missing field `at`"),
+        (
+            "tag_matching",
+            json!({ "filter": {}, "tag": "x", "dry_run": 1 }),
+            "dry_run: invalid type: integer `1`, expected a boolean",
+        ),
+        (
+            "sort_todos",
+            json!({ "by": "text", "locale": 5 }),
+            "locale: invalid type: integer `5`, expected a string",
+        ),
+        (
+            "query_todos",
+            json!({ "sort": { "by": "text", "order": "up" } }),
+            "sort.order: unknown variant `up`, expected `asc` or `desc`",
+        ),
+        (
+            "query_todos",
+            json!({ "filter": { "text_contains": 1 } }),
+            "filter.text_contains: invalid type: integer `1`, expected a string",
+        ),
+        (
+            "stale_todos",
+            json!({ "older_than_days": "30" }),
+            "older_than_days: invalid type: string \"30\", expected u32",
+        ),
+        (
+            "export_table",
+            json!({ "columns": ["id", "owner"] }),
+            "columns: unknown variant `owner`, expected one of `id`, `text`, `status`, `priority`, `due_date`",
+        ),
+        (
+            "week_view",
+            json!({ "week_start": "friday" }),
+            "week_start: unknown variant `friday`, expected `monday` or `sunday`",
+        ),
+        (
+            "streak",
+            json!({ "timezone": "Tokyo" }),
+            "timezone: invalid value: string \"Tokyo\", expected a UTC offset",
+        ),
+        (
+            "random_todo",
+            json!({ "seed": "7" }),
+            "seed: invalid type: string \"7\", expected u64",
+        ),
+        (
+            "get_lists",
+            json!({ "include_archived": "yes" }),
+            "include_archived: invalid type: string \"yes\", expected a boolean",
+        ),
+        ("list_todos_changed_since", json!({}), "missing field `since`"),
+        ("import_outline", json!({}), "missing field `text`"),
+    ] {
+        assert_eq!(
+            call(&router, tool, arguments),
+            Err(ToolError::InvalidParameters(error.to_string())),
+            "{}",
+            tool
+        );
+    }
+
+    let todo = call_json(
+        &router,
+        "set_tags",
+        json!({ "id": id, "tags": [" home ", "home", "trip"] }),
+    );
+    assert_eq!(todo["tags"], json!(["home", "trip"]));
+    let todos = call_json(
+        &router,
+        "split_todo",
+        json!({ "id": id, "texts": [" Shirts ", "Socks"] }),
+    );
+    assert_eq!(todos[0]["text"], "Shirts");
+    assert_eq!(todos[1]["tags"], json!(["home", "trip"]));
+}
+
+#[test]
+fn destructive_tools_are_flagged() {
+    let (_dir, router) = router();
+    let mut flagged: Vec<String> = mcp_server::Router::list_tools(&router)
+        .into_iter()
+        .filter(|tool| tool.input_schema["destructive"] == true)
+        .map(|tool| tool.name)
+        .collect();
+    flagged.sort();
+    let expected = [
+        "archive_list",
+        "auto_prioritize",
+        "compact",
+        "complete_matching",
+        "import_outline",
+        "import_todos",
+        "move_todos",
+        "normalize_todos",
+        "prioritize_matching",
+        "remove_tag_everywhere",
+        "remove_todo",
+        "reopen_recent",
+        "reschedule_overdue",
+        "restore_list",
+        "restore_snapshot",
+        "retag",
+        "set_list",
+        "split_todo",
+        "tag_matching",
+        "upsert_todo",
+    ];
+    assert_eq!(flagged, expected);
+}
+
+#[test]
+fn set_status_enforces_the_wip_limit() {
+    let (_dir, mut router) = router();
+    router.wip_limit = Some(1);
+    let first = add(&router, "Write the report");
+    let second = add(&router, "Review the budget");
+    let start = |id| {
+        call(
+            &router,
+            "set_status",
+            json!({ "id": id, "status": "in_progress" }),
+        )
+    };
+    start(first).unwrap();
+    // Starting a todo that is already in progress doesn't count twice.
+    start(first).unwrap();
+    match start(second) {
+        Err(ToolError::InvalidParameters(error)) => {
+            assert!(error.contains("TODO_WIP_LIMIT"), "{}", error)
+        }
+        other => panic!("expected the limit to apply, got {:?}", other),
+    }
+    call(
+        &router,
+        "set_status",
+        json!({ "id": second, "status": "blocked" }),
+    )
+    .unwrap();
+    call(
+        &router,
+        "set_status",
+        json!({ "id": first, "status": "done" }),
+    )
+    .unwrap();
+    let todo: Value = serde_json::from_str(&start(second).unwrap()).unwrap();
+    assert_eq!(todo["status"], "in_progress");
+}
+
+/// `done` and `status` agree whichever of them a tool changes.
+#[test]
+fn done_and_status_stay_in_sync() {
+    let (_dir, router) = router();
+    let status = |router: &TodoRouter<MockRuntime>, id: u64| {
+        let todos = call_json(router, "get_todos", json!({}));
+        let todo = todos
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|todo| todo["id"] == id)
+            .unwrap()
+            .clone();
+        (todo["done"].as_bool().unwrap(), todo["status"].clone())
+    };
+    let id = add(&router, "Ship it");
+    assert_eq!(status(&router, id), (false, json!("todo")));
+
+    let set = |value: &str| {
+        call(&router, "set_status", json!({ "id": id, "status": value })).unwrap();
+    };
+    set("in_progress");
+    assert_eq!(status(&router, id), (false, json!("in_progress")));
+    set("done");
+    assert_eq!(status(&router, id), (true, json!("done")));
+    set("blocked");
+    assert_eq!(status(&router, id), (false, json!("blocked")));
+
+    let update = |done: bool| {
+        call(
+            &router,
+            "update_todo",
+            json!({ "id": id, "text": "Ship it", "done": done }),
+        )
+        .unwrap();
+    };
+    update(true);
+    assert_eq!(status(&router, id), (true, json!("done")));
+    update(false);
+    assert_eq!(status(&router, id), (false, json!("todo")));
+
+    let added = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Shipped", "done": true }),
+    );
+    assert_eq!(added["status"], "done");
+    let done = added["id"].as_u64().unwrap();
+    assert_eq!(status(&router, done), (true, json!("done")));
+    let finished = call_json(
+        &router,
+        "query_todos",
+        json!({ "filter": { "status": "done" } }),
+    );
+    assert_eq!(finished.as_array().unwrap().len(), 1);
+
+    let upsert = |done: bool| {
+        call_json(
+            &router,
+            "upsert_todo",
+            json!({ "id": 7, "text": "Pushed", "done": done }),
+        )["todo"]["status"]
+            .clone()
+    };
+    assert_eq!(upsert(true), "done");
+    assert_eq!(status(&router, 7), (true, json!("done")));
+    assert_eq!(upsert(false), "todo");
+    assert_eq!(status(&router, 7), (false, json!("todo")));
+}
+
+#[test]
+fn max_batch_reads_the_environment_value() {
+    assert_eq!(parse_max_batch(None), DEFAULT_MAX_BATCH);
+    assert_eq!(parse_max_batch(Some(" 25 ")), 25);
+    for ignored in ["0", "-1", "many", ""] {
+        assert_eq!(parse_max_batch(Some(ignored)), DEFAULT_MAX_BATCH);
+    }
+}
+
+#[test]
+fn oversized_batches_are_rejected_up_front() {
+    let (_dir, mut router) = router();
+    router.max_batch = 2;
+    let id = add(&router, "Plan the trip");
+    let result = call(
+        &router,
+        "split_todo",
+        json!({ "id": id, "texts": ["Flights", "Hotel", "Car"] }),
+    );
+    assert_eq!(
+        result,
+        Err(ToolError::InvalidParameters(
+            "texts: 3 items exceed the limit of 2 per call; split the batch or raise TODO_MAX_BATCH"
+                .to_string()
+        ))
+    );
+    // Nothing was split.
+    let todos = call_json(&router, "get_todos", json!({}));
+    assert_eq!(todos.as_array().unwrap().len(), 1);
+
+    let todos = call_json(
+        &router,
+        "split_todo",
+        json!({ "id": id, "texts": ["Flights", "Hotel"] }),
+    );
+    assert_eq!(todos.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn timestamp_ids_refuse_a_clock_before_1970() {
+    let epoch = DateTime::<Utc>::UNIX_EPOCH;
+    assert_eq!(timestamp_id(epoch).unwrap(), 0);
+    assert_eq!(
+        timestamp_id(epoch + chrono::Duration::milliseconds(1500)).unwrap(),
+        1500
+    );
+    match timestamp_id(epoch - chrono::Duration::milliseconds(1)) {
+        Err(ToolError::ExecutionError(error)) => assert!(error.contains("1969"), "{}", error),
+        other => panic!("expected a clock error, got {:?}", other),
+    }
+}
+
+fn sorted_texts(texts: &[&str], locale: Option<&str>) -> Vec<String> {
+    let mut todos: Vec<Todo> = texts
+        .iter()
+        .map(|text| Todo {
+            text: text.to_string(),
+            ..Default::default()
+        })
+        .collect();
+    sort_todos(&mut todos, "text", false, locale).unwrap();
+    todos.into_iter().map(|todo| todo.text).collect()
+}
+
+#[test]
+fn accented_text_sorts_with_its_base_letter() {
+    // By code point, `é` would sort after `z`.
+    assert_eq!(
+        sorted_texts(&["zèbre", "éclair", "Avion", "ecole"], None),
+        ["Avion", "éclair", "ecole", "zèbre"]
+    );
+    assert_eq!(
+        sorted_texts(&["zèbre", "éclair", "Avion"], Some("fr")),
+        ["Avion", "éclair", "zèbre"]
+    );
+}
+
+#[test]
+fn japanese_text_sorts_in_kana_order() {
+    // Katakana code points all come after hiragana ones, but collation
+    // interleaves them by sound: い, カ, さ.
+    assert_eq!(
+        sorted_texts(&["さくら", "カメラ", "いぬ"], Some("ja")),
+        ["いぬ", "カメラ", "さくら"]
+    );
+    assert_eq!(
+        sorted_texts(&["さくら", "カメラ", "いぬ"], None),
+        ["いぬ", "カメラ", "さくら"]
+    );
+}
+
+#[test]
+fn sort_todos_rejects_a_malformed_locale() {
+    let mut todos = vec![Todo::default()];
+    assert_eq!(
+        sort_todos(&mut todos, "text", false, Some("not a locale!")),
+        Err(ToolError::InvalidParameters("locale".to_string()))
+    );
+}
+
+/// What goes on an agenda: open todos due before the day (overdue), during
+/// it (due today) and, of the rest, the high-priority ones, each section in
+/// due date order.
+#[test]
+fn agenda_includes_overdue_due_and_high_priority_todos() {
+    let (_dir, router) = router();
+    let date = NaiveDate::from_ymd_opt(2030, 6, 15).unwrap();
+    let at = |day: NaiveDate, hour: u32| {
+        (start_of_day(day) + chrono::Duration::hours(hour.into())).to_rfc3339()
+    };
+    let day_before = date.pred_opt().unwrap();
+    let day_after = date.succ_opt().unwrap();
+    let add_with = |arguments: Value| call_json(&router, "add_todo", arguments);
+    let lunch = add_with(json!({ "text": "Lunch", "due_date": at(date, 12) }));
+    let call_mom = add_with(json!({ "text": "Call mom", "due_date": at(day_before, 12) }));
+    let roof = add_with(json!({ "text": "Fix roof", "priority": "high" }));
+    let dentist = add_with(json!({ "text": "Dentist", "due_date": at(date, 9) }));
+    let taxes = add_with(json!({
+        "text": "File taxes",
+        "due_date": at(day_before, 0),
+        "priority": "high"
+    }));
+    add_with(json!({ "text": "Vacation", "due_date": at(day_after, 0) }));
+    add_with(json!({ "text": "Someday", "priority": "low" }));
+    add_with(json!({ "text": "Paid", "due_date": at(date, 10), "done": true }));
+    let review = add_with(json!({
+        "text": "Quarterly review",
+        "due_date": at(day_after, 9),
+        "priority": "high"
+    }));
+
+    let line = |position: usize, todo: &Value| {
+        format_agenda_item(position, &serde_json::from_value(todo.clone()).unwrap())
+    };
+    let expected = format!(
+        "Agenda for 2030-06-15\n\nOverdue:\n{}\n{}\n\nDue today:\n{}\n{}\n\nHigh priority:\n{}\n{}",
+        line(1, &taxes),
+        line(2, &call_mom),
+        line(3, &dentist),
+        line(4, &lunch),
+        line(5, &roof),
+        line(6, &review),
+    );
+    assert_eq!(
+        call(&router, "agenda", json!({ "date": "2030-06-15" })).unwrap(),
+        expected
+    );
+
+    assert_eq!(
+        call(
+            &router,
+            "agenda",
+            json!({ "date": "2030-06-15", "list": "empty" })
+        )
+        .unwrap(),
+        "Agenda for 2030-06-15\n\nNothing on the agenda."
+    );
+}
+
+fn ids(router: &TodoRouter<MockRuntime>) -> Vec<u64> {
+    ids_in(router, DEFAULT_LIST)
+}
+
+fn ids_in(router: &TodoRouter<MockRuntime>, list: &str) -> Vec<u64> {
+    call_json(router, "get_todos", json!({ "list": list }))
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|todo| todo["id"].as_u64().unwrap())
+        .collect()
+}
+
+/// `get_todos` returns todos in insertion order, and tools that edit a todo
+/// leave it where it was.
+#[test]
+fn edits_keep_the_insertion_order() {
+    let (_dir, router) = router();
+    let [a, b, c, d] = ["a", "b", "c", "d"].map(|text| add(&router, text));
+    assert_eq!(ids(&router), [a, b, c, d]);
+
+    let edits = [
+        ("update_todo", json!({ "id": a, "text": "A", "done": true })),
+        ("set_priority", json!({ "id": c, "priority": "high" })),
+        ("set_tags", json!({ "id": b, "tags": ["home"] })),
+        ("set_notes", json!({ "id": d, "notes": "soon" })),
+        (
+            "set_due_date",
+            json!({ "id": b, "due_date": "2030-01-01T00:00:00Z" }),
+        ),
+        ("add_subtask", json!({ "id": c, "text": "step" })),
+        ("set_status", json!({ "id": d, "status": "blocked" })),
+        (
+            "update_todo",
+            json!({ "id": a, "text": "A", "done": false }),
+        ),
+        ("bump_todo", json!({ "id": c })),
+    ];
+    for (tool, arguments) in edits {
+        call(&router, tool, arguments).unwrap();
+        assert_eq!(ids(&router), [a, b, c, d], "after {}", tool);
+    }
+
+    call(&router, "remove_todo", json!({ "id": b })).unwrap();
+    assert_eq!(ids(&router), [a, c, d]);
+    let e = add(&router, "e");
+    assert_eq!(ids(&router), [a, c, d, e]);
+    let f = call_json(&router, "add_todo", json!({ "text": "f", "position": 1 }))["id"]
+        .as_u64()
+        .unwrap();
+    assert_eq!(ids(&router), [a, f, c, d, e]);
+}
+
+/// Keys this version doesn't know survive a load and save of their todo.
+#[test]
+fn unknown_todo_fields_survive_a_rewrite() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = dir.path().join(STORE_PATH);
+    std::fs::write(
+        &store,
+        json!({
+            "todos": [{
+                "id": 1,
+                "text": "Water the plants",
+                "done": false,
+                "color": "green",
+                "sync": { "etag": "abc", "remote_id": 7 }
+            }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let router = router_in(dir.path());
+    call(
+        &router,
+        "update_todo",
+        json!({ "id": 1, "text": "Water the cactus", "done": true }),
+    )
+    .unwrap();
+
+    let todos = call_json(&router, "get_todos", json!({}));
+    assert_eq!(todos[0]["color"], "green");
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&store).unwrap()).unwrap();
+    let todo = &saved["todos"][0];
+    assert_eq!(todo["text"], "Water the cactus");
+    assert_eq!(todo["done"], true);
+    assert_eq!(todo["color"], "green");
+    assert_eq!(todo["sync"], json!({ "etag": "abc", "remote_id": 7 }));
+}
+
+/// Storage over a full disk: reads work, every write fails.
+struct FullDisk<S>(S);
+
+impl<S: TodoStorage> TodoStorage for FullDisk<S> {
+    fn lists(&self) -> Result<Vec<String>, ToolError> {
+        self.0.lists()
+    }
+    fn has_list(&self, list: &str) -> Result<bool, ToolError> {
+        self.0.has_list(list)
+    }
+    fn load(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        self.0.load(list)
+    }
+    fn save(&self, _list: &str, _todos: &[Todo]) -> Result<(), ToolError> {
+        let e = std::io::Error::from(std::io::ErrorKind::StorageFull);
+        Err(persist_error(io_failure_reason(e.kind()), e))
+    }
+    fn remove(&self, _list: &str) -> Result<(), ToolError> {
+        self.save("", &[])
+    }
+    fn path(&self) -> Result<PathBuf, ToolError> {
+        self.0.path()
+    }
+    fn kind(&self) -> &'static str {
+        self.0.kind()
+    }
+}
+
+#[test]
+fn failed_saves_are_reported_and_change_nothing() {
+    let (dir, mut router) = router();
+    let id = add(&router, "Back up photos");
+    router.storage = Arc::new(FullDisk(JsonStorage::new(router.app.clone())));
+
+    for (tool, arguments) in [
+        ("add_todo", json!({ "text": "Buy a bigger disk" })),
+        (
+            "update_todo",
+            json!({ "id": id, "text": "Done", "done": true }),
+        ),
+        ("remove_todo", json!({ "id": id })),
+        (
+            "split_todo",
+            json!({ "id": id, "texts": ["Sort", "Upload"] }),
+        ),
+    ] {
+        match call(&router, tool, arguments) {
+            Err(ToolError::ExecutionError(error)) => assert!(
+                error.starts_with("could not persist changes: the disk is full"),
+                "{}: {}",
+                tool,
+                error
+            ),
+            other => panic!("{} should have failed, got {:?}", tool, other),
+        }
+    }
+    let todos = call_json(&router, "get_todos", json!({}));
+    assert_eq!(todos.as_array().unwrap().len(), 1);
+    assert_eq!(todos[0]["text"], "Back up photos");
+    assert_eq!(todos[0]["done"], false);
+    // Sync clients aren't told about deletions that didn't happen.
+    let saved: Value =
+        serde_json::from_slice(&std::fs::read(dir.path().join(STORE_PATH)).unwrap()).unwrap();
+    assert!(saved.get("deleted:default").is_none(), "{}", saved);
+}
+
+/// Only `create_list` and `set_list` with `create` make lists; other writes
+/// to a list that doesn't exist fail, and writes that change nothing don't
+/// save at all.
+#[test]
+fn writes_never_create_lists() {
+    let (_dir, mut router) = router();
+    for (tool, arguments) in [
+        ("add_todo", json!({ "text": "Lost", "list": "nowhere" })),
+        (
+            "import_outline",
+            json!({ "text": "- Lost", "list": "nowhere" }),
+        ),
+        (
+            "set_list",
+            json!({ "id": 1, "to": "default", "list": "nowhere" }),
+        ),
+    ] {
+        assert_eq!(
+            call(&router, tool, arguments),
+            Err(ToolError::InvalidParameters(
+                "list nowhere not found".to_string()
+            )),
+            "{}",
+            tool
+        );
+    }
+    assert_eq!(router.get_lists().unwrap(), [DEFAULT_LIST]);
+
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    let id = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Plan", "list": "work" }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    call(
+        &router,
+        "set_list",
+        json!({ "id": id, "list": "work", "to": "later", "create": true }),
+    )
+    .unwrap();
+    assert_eq!(router.get_lists().unwrap(), [DEFAULT_LIST, "later", "work"]);
+
+    let id = add(&router, "Stay put");
+    router.storage = Arc::new(FullDisk(JsonStorage::new(router.app.clone())));
+    for (tool, arguments) in [
+        (
+            "update_todo",
+            json!({ "id": id, "text": "Stay put", "done": false }),
+        ),
+        (
+            "update_todo",
+            json!({ "id": id + 1, "text": "Missing", "done": true }),
+        ),
+        ("remove_todo", json!({ "id": id + 1 })),
+    ] {
+        assert!(call(&router, tool, arguments).is_ok(), "{}", tool);
+    }
+}
+
+#[test]
+fn save_store_reports_a_write_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = app_in(dir.path());
+    let store = app.store("unwritable.json").unwrap();
+    store.set("key", json!("value"));
+    // A directory where the file should be can't be written, even by root.
+    std::fs::create_dir(dir.path().join("unwritable.json")).unwrap();
+    match save_store(&store) {
+        Err(ToolError::ExecutionError(error)) => {
+            assert!(error.starts_with("could not persist changes"), "{}", error)
+        }
+        other => panic!("expected a persist error, got {:?}", other),
+    }
+    assert_eq!(
+        io_failure_reason(std::io::ErrorKind::ReadOnlyFilesystem),
+        Some("the store is read-only")
+    );
+    assert_eq!(io_failure_reason(std::io::ErrorKind::Interrupted), None);
+}
+
+#[test]
+fn add_todo_can_create_a_completed_todo() {
+    let (_dir, router) = router();
+    let done = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Filed", "done": true }),
+    );
+    assert_eq!(done["done"], true);
+    assert_eq!(done["completed_at"], done["created_at"]);
+    let open = call_json(&router, "add_todo", json!({ "text": "To file" }));
+    assert_eq!(open["done"], false);
+    assert_eq!(open["completed_at"], Value::Null);
+
+    let todos = call_json(&router, "done_todos", json!({}));
+    assert_eq!(todos.as_array().unwrap().len(), 1);
+    assert_eq!(todos[0]["id"], done["id"]);
+}
+
+#[test]
+fn transient_store_failures_are_retried() {
+    let mut attempts = 0;
+    let result = retry_store("reload", || {
+        attempts += 1;
+        if attempts == 1 {
+            Err(tauri_plugin_store::Error::Deserialize(
+                "EOF while parsing".into(),
+            ))
+        } else {
+            Ok(attempts)
+        }
+    });
+    assert_eq!(result.unwrap(), 2);
+
+    let mut attempts = 0;
+    let result: tauri_plugin_store::Result<()> = retry_store("save", || {
+        attempts += 1;
+        Err(std::io::Error::from(std::io::ErrorKind::StorageFull).into())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts, 1, "a full disk is not retried");
+}
+
+/// A read that lands while another process is halfway through rewriting
+/// `store.json` succeeds once the file is whole again.
+#[test]
+fn reads_wait_out_a_half_written_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let router = router_in(dir.path());
+    add(&router, "Survive the rewrite");
+    let store = dir.path().join(STORE_PATH);
+    let whole = std::fs::read_to_string(&store).unwrap();
+    std::fs::write(&store, &whole[..whole.len() / 2]).unwrap();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(&store, whole).unwrap();
+    });
+    let todos = call_json(&router, "get_todos", json!({}));
+    writer.join().unwrap();
+    assert_eq!(todos[0]["text"], "Survive the rewrite");
+}
+
+fn todo(id: u64, text: &str) -> Todo {
+    Todo {
+        id,
+        text: text.to_string(),
+        ..Default::default()
+    }
+}
+
+fn texts(todos: &[Todo]) -> Vec<&str> {
+    todos.iter().map(|todo| todo.text.as_str()).collect()
+}
+
+/// What `TodoRouter` relies on from every backend.
+fn check_storage(storage: &dyn TodoStorage) {
+    assert!(!storage.has_list("work").unwrap());
+    assert!(storage.load("work").unwrap().is_empty());
+
+    let mut todos = vec![todo(2, "second"), todo(1, "first"), todo(3, "third")];
+    todos[1].done = true;
+    todos[1]
+        .extra
+        .insert("color".to_string(), Value::from("green"));
+    storage.save("work", &todos).unwrap();
+    assert!(storage.has_list("work").unwrap());
+    assert!(storage.lists().unwrap().contains(&"work".to_string()));
+    let loaded = storage.load("work").unwrap();
+    // Stored order, not id order.
+    assert_eq!(texts(&loaded), ["second", "first", "third"]);
+    assert!(loaded[1].done);
+    assert_eq!(loaded[1].extra["color"], "green");
+
+    // A save replaces the whole list and leaves other lists alone.
+    storage.save(DEFAULT_LIST, &[todo(9, "elsewhere")]).unwrap();
+    storage.save("work", &[todo(3, "third")]).unwrap();
+    assert_eq!(texts(&storage.load("work").unwrap()), ["third"]);
+    assert_eq!(texts(&storage.load(DEFAULT_LIST).unwrap()), ["elsewhere"]);
+
+    storage.save("work", &[]).unwrap();
+    assert!(storage.has_list("work").unwrap());
+    storage.remove("work").unwrap();
+    assert!(!storage.has_list("work").unwrap());
+    assert!(!storage.lists().unwrap().contains(&"work".to_string()));
+    assert!(storage.path().unwrap().exists());
+}
+
+#[test]
+fn json_storage_keeps_lists() {
+    let (_dir, router) = router();
+    let storage = JsonStorage::new(router.app.clone());
+    assert_eq!(storage.kind(), "json");
+    check_storage(&storage);
+}
+
+#[test]
+fn sqlite_storage_keeps_lists() {
+    let (dir, router) = router();
+    let json = JsonStorage::new(router.app.clone());
+    let storage = SqliteStorage::open(dir.path().join(SQLITE_FILE), &json).unwrap();
+    assert_eq!(storage.kind(), "sqlite");
+    check_storage(&storage);
+}
+
+/// The first open of the SQLite store copies every list from `store.json`;
+/// later opens leave the database as it is.
+#[test]
+fn sqlite_storage_migrates_store_json_once() {
+    let (dir, router) = router();
+    let json = JsonStorage::new(router.app.clone());
+    json.save(
+        DEFAULT_LIST,
+        &[todo(1, "default one"), todo(2, "default two")],
+    )
+    .unwrap();
+    json.save("work", &[todo(3, "work one")]).unwrap();
+    let path = dir.path().join(SQLITE_FILE);
+
+    let sqlite = SqliteStorage::open(path.clone(), &json).unwrap();
+    assert_eq!(
+        texts(&sqlite.load(DEFAULT_LIST).unwrap()),
+        ["default one", "default two"]
+    );
+    assert_eq!(texts(&sqlite.load("work").unwrap()), ["work one"]);
+    // `store.json` is left as it was.
+    assert_eq!(texts(&json.load("work").unwrap()), ["work one"]);
+    sqlite.save("work", &[todo(4, "work two")]).unwrap();
+    drop(sqlite);
+
+    json.save("home", &[todo(5, "added later")]).unwrap();
+    let sqlite = SqliteStorage::open(path, &json).unwrap();
+    assert_eq!(texts(&sqlite.load("work").unwrap()), ["work two"]);
+    assert!(!sqlite.has_list("home").unwrap());
+}
+
+#[test]
+fn backup_intervals_that_overflow_disable_backups() {
+    assert_eq!(parse_backup_interval(None), None);
+    assert_eq!(parse_backup_interval(Some("0")), None);
+    assert_eq!(
+        parse_backup_interval(Some(" 30 ")),
+        Some(Duration::from_secs(30 * 60))
+    );
+    assert_eq!(parse_backup_interval(Some(&u64::MAX.to_string())), None);
+    assert_eq!(
+        parse_backup_interval(Some(&(u64::MAX / 60).to_string())),
+        None
+    );
+}
+
+/// `export_ics` rejects an `include_all` that isn't a boolean instead of
+/// quietly exporting only the dated todos.
+#[test]
+fn export_ics_checks_include_all() {
+    let (_dir, router) = router();
+    add(&router, "Undated");
+    assert_eq!(
+        call(&router, "export_ics", json!({ "include_all": "yes" })),
+        Err(ToolError::InvalidParameters(
+            "include_all: invalid type: string \"yes\", expected a boolean".to_string()
+        ))
+    );
+    let ics = call(&router, "export_ics", json!({ "include_all": true })).unwrap();
+    assert!(ics.contains("SUMMARY:Undated"), "{}", ics);
+}
+
+/// Done todos export as completed VTODOs carrying when they were finished.
+#[test]
+fn export_ics_marks_done_todos_completed() {
+    let (_dir, router) = router();
+    add(&router, "Open");
+    let done = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Filed", "done": true }),
+    );
+    let completed = parse_date(done["completed_at"].as_str().unwrap()).unwrap();
+    let ics = call(&router, "export_ics", json!({ "include_all": true })).unwrap();
+    assert!(
+        ics.contains(&format!(
+            "SUMMARY:Filed\r\nSTATUS:COMPLETED\r\nCOMPLETED:{}\r\n",
+            ics_timestamp(completed.into())
+        )),
+        "{}",
+        ics
+    );
+    assert!(
+        ics.contains("SUMMARY:Open\r\nSTATUS:NEEDS-ACTION\r\nEND:VTODO"),
+        "{}",
+        ics
+    );
+}
+
+/// Writes to `store.json` outside the todo lists start from what is on disk,
+/// so they don't drop what another process saved in the meantime.
+#[test]
+fn store_writes_keep_other_processes_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let app = router_in(dir.path());
+    let mut mcp = router_in(dir.path());
+    mcp.write_lock = Arc::default();
+    let saved = || -> Value {
+        serde_json::from_slice(&std::fs::read(dir.path().join(STORE_PATH)).unwrap()).unwrap()
+    };
+    let id = add(&app, "Focus");
+    call(&mcp, "get_todos", json!({})).unwrap();
+
+    let settings = ListSettings {
+        default_due_offset: Some("3d".to_string()),
+    };
+    mcp.save_list_settings(DEFAULT_LIST, &settings).unwrap();
+    app.record_deletions(DEFAULT_LIST, &[7]).unwrap();
+    assert_eq!(saved()["settings:default"]["default_due_offset"], "3d");
+    app.set_focus(DEFAULT_LIST, id).unwrap();
+    mcp.save_list_settings(DEFAULT_LIST, &ListSettings::default())
+        .unwrap();
+    assert_eq!(saved()["deleted:default"][0]["id"], 7);
+    assert_eq!(saved()["focus:default"], id);
+    mcp.record_deletions(DEFAULT_LIST, &[8]).unwrap();
+    app.clear_focus(DEFAULT_LIST).unwrap();
+    assert_eq!(saved()["deleted:default"].as_array().unwrap().len(), 2);
+    assert!(saved().get("focus:default").is_none());
+}
+
+/// `archive_list` sees an archive another process just made, even when the
+/// todos live in SQLite and nothing else re-read `store.json`.
+#[test]
+fn archive_list_sees_other_processes_archives() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = router_in(dir.path());
+    let mut mcp = router_in(dir.path());
+    mcp.write_lock = Arc::default();
+    for router in [&mut app, &mut mcp] {
+        let json = JsonStorage::new(router.app.clone());
+        router.storage =
+            Arc::new(SqliteStorage::open(dir.path().join(SQLITE_FILE), &json).unwrap());
+    }
+    call(&app, "create_list", json!({ "name": "work" })).unwrap();
+    call(&app, "add_todo", json!({ "text": "first", "list": "work" })).unwrap();
+    call(&app, "archive_list", json!({ "name": "work" })).unwrap();
+
+    mcp.storage.save("work", &[todo(2, "second")]).unwrap();
+    assert_eq!(
+        call(&mcp, "archive_list", json!({ "name": "work" })),
+        Err(ToolError::InvalidParameters(
+            "an archived list named work already exists".to_string()
+        ))
+    );
+}
+
+/// A list's settings, focus, tombstones and snapshots go into the archive
+/// with it and come back on restore.
+#[test]
+fn archive_list_keeps_the_lists_data() {
+    let (_dir, router) = router();
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    let kept = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Kept", "list": "work" }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    let gone = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Gone", "list": "work" }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    call(
+        &router,
+        "remove_todo",
+        json!({ "id": gone, "list": "work" }),
+    )
+    .unwrap();
+    call(
+        &router,
+        "set_list_settings",
+        json!({ "default_due_offset": "+3 days", "list": "work" }),
+    )
+    .unwrap();
+    call(&router, "set_focus", json!({ "id": kept, "list": "work" })).unwrap();
+    call(
+        &router,
+        "create_snapshot",
+        json!({ "name": "before", "list": "work" }),
+    )
+    .unwrap();
+
+    call(&router, "archive_list", json!({ "name": "work" })).unwrap();
+    let store = router.app.store(STORE_PATH).unwrap();
+    for prefix in LIST_DATA_KEY_PREFIXES {
+        assert!(!store.has(format!("{}work", prefix)), "{}work", prefix);
+    }
+
+    call(&router, "restore_list", json!({ "name": "work" })).unwrap();
+    assert_eq!(
+        call_json(&router, "get_list_settings", json!({ "list": "work" })),
+        json!({ "default_due_offset": "+3 days" })
+    );
+    assert_eq!(
+        call_json(&router, "get_focus", json!({ "list": "work" }))["id"],
+        kept
+    );
+    assert_eq!(
+        call_json(&router, "list_snapshots", json!({ "list": "work" }))[0]["name"],
+        "before"
+    );
+    let tombstones: Vec<u64> = router
+        .get_tombstones("work")
+        .unwrap()
+        .iter()
+        .map(|tombstone| tombstone.id)
+        .collect();
+    assert_eq!(tombstones, [gone]);
+}
+
+/// `#n` references in id arrays resolve in the list the tool reads, and
+/// strings that are neither ids nor references are rejected by name.
+#[test]
+fn id_arrays_accept_references() {
+    let (_dir, router) = router();
+    let first = add(&router, "First");
+    let second = add(&router, "Second");
+    let todo = call_json(
+        &router,
+        "set_dependencies",
+        json!({ "id": second, "depends_on": ["#1"] }),
+    );
+    assert_eq!(todo["depends_on"], json!([first]));
+    assert_eq!(
+        call(
+            &router,
+            "set_dependencies",
+            json!({ "id": second, "depends_on": ["#1", "abc"] }),
+        ),
+        Err(ToolError::InvalidParameters("depends_on".to_string()))
+    );
+
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    assert_eq!(
+        call(
+            &router,
+            "move_todos",
+            json!({ "ids": ["#2", "abc"], "from": "default", "to": "work" }),
+        ),
+        Err(ToolError::InvalidParameters("ids".to_string()))
+    );
+    let moved = call_json(
+        &router,
+        "move_todos",
+        json!({ "ids": ["#2"], "from": "default", "to": "work" }),
+    );
+    assert_eq!(moved["moved"], json!([second]));
+}
+
+/// Storage where writes to one list fail, as if only that list's file were
+/// on a full disk.
+struct FullList<S>(S, &'static str);
+
+impl<S: TodoStorage> TodoStorage for FullList<S> {
+    fn lists(&self) -> Result<Vec<String>, ToolError> {
+        self.0.lists()
+    }
+    fn has_list(&self, list: &str) -> Result<bool, ToolError> {
+        self.0.has_list(list)
+    }
+    fn load(&self, list: &str) -> Result<Vec<Todo>, ToolError> {
+        self.0.load(list)
+    }
+    fn save(&self, list: &str, todos: &[Todo]) -> Result<(), ToolError> {
+        if list == self.1 {
+            let e = std::io::Error::from(std::io::ErrorKind::StorageFull);
+            return Err(persist_error(io_failure_reason(e.kind()), e));
+        }
+        self.0.save(list, todos)
+    }
+    fn remove(&self, list: &str) -> Result<(), ToolError> {
+        self.0.remove(list)
+    }
+    fn path(&self) -> Result<PathBuf, ToolError> {
+        self.0.path()
+    }
+    fn kind(&self) -> &'static str {
+        self.0.kind()
+    }
+}
+
+#[test]
+fn move_todos_moves_and_reports_missing_ids() {
+    let (_dir, router) = router();
+    let keep = add(&router, "Keep");
+    let first = add(&router, "First");
+    let second = add(&router, "Second");
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    // Ids are only unique within a list, so pick one that can't clash.
+    call(
+        &router,
+        "upsert_todo",
+        json!({ "id": 1, "text": "Already there", "list": "work" }),
+    )
+    .unwrap();
+
+    let result = call_json(
+        &router,
+        "move_todos",
+        json!({ "ids": [second, first, 42], "from": "default", "to": "work" }),
+    );
+    assert_eq!(
+        result,
+        json!({ "moved": [first, second], "not_found": [42] })
+    );
+    assert_eq!(ids(&router), [keep]);
+    let work = call_json(&router, "get_todos", json!({ "list": "work" }));
+    let work: Vec<_> = work
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|todo| {
+            (
+                todo["text"].as_str().unwrap(),
+                todo["number"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(work, [("Already there", 1), ("First", 2), ("Second", 3)]);
+    let changes = call_json(
+        &router,
+        "list_todos_changed_since",
+        json!({ "since": "2000-01-01T00:00:00Z" }),
+    );
+    assert_eq!(changes["deleted"], json!([first, second]));
+}
+
+#[test]
+fn move_todos_checks_the_destination() {
+    let (_dir, router) = router();
+    let id = add(&router, "Stay");
+    for (arguments, error) in [
+        (
+            json!({ "ids": [id], "from": "default", "to": "default" }),
+            "from and to must be different lists",
+        ),
+        (
+            json!({ "ids": [id], "from": "default", "to": "nowhere" }),
+            "list nowhere not found",
+        ),
+    ] {
+        assert_eq!(
+            call(&router, "move_todos", arguments),
+            Err(ToolError::InvalidParameters(error.to_string()))
+        );
+    }
+    assert!(!router.get_lists().unwrap().contains(&"nowhere".to_string()));
+
+    // `upsert_todo` can put the same id into another list.
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    call(
+        &router,
+        "upsert_todo",
+        json!({ "id": id, "text": "Clash", "list": "work" }),
+    )
+    .unwrap();
+    assert_eq!(
+        call(
+            &router,
+            "move_todos",
+            json!({ "ids": [id], "from": "default", "to": "work" }),
+        ),
+        Err(ToolError::InvalidParameters(format!(
+            "list work already has a todo with id {}",
+            id
+        )))
+    );
+    assert_eq!(ids(&router), [id]);
+    assert_eq!(ids_in(&router, "work"), [id]);
+}
+
+/// A destination that can't be saved leaves the todos in the source.
+#[test]
+fn move_todos_keeps_the_source_when_the_destination_fails() {
+    let (_dir, mut router) = router();
+    let id = add(&router, "Stay");
+    call(&router, "create_list", json!({ "name": "work" })).unwrap();
+    router.storage = Arc::new(FullList(JsonStorage::new(router.app.clone()), "work"));
+    assert!(matches!(
+        call(
+            &router,
+            "move_todos",
+            json!({ "ids": [id], "from": "default", "to": "work" }),
+        ),
+        Err(ToolError::ExecutionError(_))
+    ));
+    assert_eq!(ids(&router), [id]);
+    let changes = call_json(
+        &router,
+        "list_todos_changed_since",
+        json!({ "since": "2000-01-01T00:00:00Z" }),
+    );
+    assert_eq!(changes["deleted"], json!([]));
+}
+
+/// With `chunk_size`, the list comes as several JSON arrays that add up to
+/// the single array returned without it.
+#[test]
+fn chunked_results_add_up_to_the_whole_list() {
+    let (_dir, router) = router();
+    let blocks = |arguments: Value| -> Vec<Value> {
+        tauri::async_runtime::block_on(mcp_server::Router::call_tool(
+            &router,
+            "get_todos",
+            arguments,
+        ))
+        .unwrap()
+        .iter()
+        .map(|content| serde_json::from_str(content.as_text().unwrap()).unwrap())
+        .collect()
+    };
+    assert_eq!(blocks(json!({ "chunk_size": 2 })), [json!([])]);
+    for n in 0..5 {
+        add(&router, &format!("todo {}", n));
+    }
+    let chunks = blocks(json!({ "chunk_size": 2 }));
+    let lengths: Vec<usize> = chunks
+        .iter()
+        .map(|chunk| chunk.as_array().unwrap().len())
+        .collect();
+    assert_eq!(lengths, [2, 2, 1]);
+    let joined: Vec<Value> = chunks
+        .into_iter()
+        .flat_map(|chunk| chunk.as_array().unwrap().clone())
+        .collect();
+    assert_eq!(
+        Value::Array(joined),
+        call_json(&router, "get_todos", json!({}))
+    );
+
+    for (arguments, error) in [
+        (
+            json!({ "chunk_size": 0 }),
+            "chunk_size: invalid value: integer `0`, expected at least 1",
+        ),
+        (
+            json!({ "chunk_size": 2, "limit": 2 }),
+            "chunk_size can't be combined with offset or limit",
+        ),
+    ] {
+        assert_eq!(
+            call(&router, "get_todos", arguments),
+            Err(ToolError::InvalidParameters(error.to_string()))
+        );
+    }
+}
+
+/// Every tool that selects todos like `query_todos` takes the same `filter`
+/// object, and those that change the matches refuse to run without one.
+#[test]
+fn bulk_tools_share_a_required_filter() {
+    let (_dir, router) = router();
+    let urgent = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Pay rent", "tags": ["urgent"] }),
+    )["id"]
+        .clone();
+    add(&router, "Water plants");
+    let filter = json!({ "tags": ["urgent"] });
+    for (tool, arguments) in [
+        ("query_todos", json!({ "filter": filter })),
+        ("list_ids", json!({ "filter": filter })),
+        (
+            "complete_matching",
+            json!({ "filter": filter, "dry_run": true }),
+        ),
+        (
+            "prioritize_matching",
+            json!({ "filter": filter, "priority": "high", "dry_run": true }),
+        ),
+        (
+            "tag_matching",
+            json!({ "filter": filter, "tag": "rent", "dry_run": true }),
+        ),
+    ] {
+        let result = call_json(&router, tool, arguments);
+        let matched: Vec<&Value> = match &result {
+            Value::Array(items) => items
+                .iter()
+                .map(|item| item.get("id").unwrap_or(item))
+                .collect(),
+            result => result["todos"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|todo| &todo["id"])
+                .collect(),
+        };
+        assert_eq!(matched, [&urgent], "{}", tool);
+    }
+    for (tool, arguments) in [
+        ("complete_matching", json!({})),
+        ("prioritize_matching", json!({ "priority": "high" })),
+        ("tag_matching", json!({ "tag": "rent" })),
+    ] {
+        assert_eq!(
+            call(&router, tool, arguments),
+            Err(ToolError::InvalidParameters(
+                "missing field `filter`".to_string()
+            )),
+            "{}",
+            tool
+        );
+    }
+    let all = call_json(
+        &router,
+        "prioritize_matching",
+        json!({ "filter": {}, "priority": "low", "dry_run": true }),
+    );
+    assert_eq!(all["count"], 2);
+}
+
+#[test]
+fn split_todo_replaces_the_todo_in_place() {
+    let (_dir, router) = router();
+    add(&router, "Before");
+    let id = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Move house", "priority": "high", "tags": ["home"] }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    add(&router, "After");
+    let parts = call_json(
+        &router,
+        "split_todo",
+        json!({ "id": id, "texts": ["Pack", "Hire a van"] }),
+    );
+    let todos = router.get_todos(DEFAULT_LIST).unwrap();
+    assert_eq!(texts(&todos), ["Before", "Pack", "Hire a van", "After"]);
+    for (part, todo) in parts.as_array().unwrap().iter().zip(&todos[1..3]) {
+        assert_eq!(part["id"], todo.id);
+        assert_ne!(todo.id, id);
+        assert_eq!(todo.priority, Some(Priority::High));
+        assert_eq!(todo.tags, ["home"]);
+    }
+    assert_eq!(todos[1].number, Some(4));
+    assert_eq!(todos[2].number, Some(5));
+    // Sync clients learn that the original is gone.
+    let changes = call_json(
+        &router,
+        "list_todos_changed_since",
+        json!({ "since": "2000-01-01T00:00:00Z" }),
+    );
+    assert_eq!(changes["deleted"], json!([id]));
+    assert_eq!(
+        call(
+            &router,
+            "split_todo",
+            json!({ "id": id, "texts": ["Again"] })
+        ),
+        Err(ToolError::InvalidParameters(format!(
+            "todo {} not found",
+            id
+        )))
+    );
+}
+
+#[test]
+fn import_outline_adds_todos_with_subtasks() {
+    let (_dir, router) = router();
+    add(&router, "Existing");
+    let counts = call_json(
+        &router,
+        "import_outline",
+        json!({ "text": "- Pack\n  - [x] Socks\n\t* Shirts\n\n* [x] Book hotel\nCall mom\n" }),
+    );
+    assert_eq!(counts, json!({ "todos": 3, "subtasks": 2 }));
+    let todos = router.get_todos(DEFAULT_LIST).unwrap();
+    assert_eq!(
+        texts(&todos),
+        ["Existing", "Pack", "Book hotel", "Call mom"]
+    );
+    assert_eq!(
+        serde_json::to_value(&todos[1].subtasks).unwrap(),
+        json!([
+            { "text": "Socks", "done": true },
+            { "text": "Shirts", "done": false }
+        ])
+    );
+    assert!(!todos[1].done);
+    assert!(todos[2].done);
+    assert!(todos[2].completed_at.is_some());
+    assert_eq!(todos[3].number, Some(4));
+
+    for (text, error) in [
+        (
+            "  - Orphan",
+            "text: line 1 is indented but no todo comes before it",
+        ),
+        ("\n - [ ] \n", "text: the outline has no items"),
+    ] {
+        assert_eq!(
+            call(&router, "import_outline", json!({ "text": text })),
+            Err(ToolError::InvalidParameters(error.to_string()))
+        );
+    }
+    assert_eq!(router.get_todos(DEFAULT_LIST).unwrap().len(), 4);
+}
+
+#[test]
+fn diff_snapshots_reports_added_removed_and_changed_todos() {
+    let (_dir, router) = router();
+    let kept = add(&router, "Draft");
+    let removed = add(&router, "Scrap");
+    call(&router, "create_snapshot", json!({ "name": "monday" })).unwrap();
+    call(
+        &router,
+        "update_todo",
+        json!({ "id": kept, "text": "Final", "done": false }),
+    )
+    .unwrap();
+    call(&router, "remove_todo", json!({ "id": removed })).unwrap();
+    let added = add(&router, "New");
+
+    let diff = call_json(&router, "diff_snapshots", json!({ "from": "monday" }));
+    assert_eq!(diff["from"], "monday");
+    assert_eq!(diff["to"], "current");
+    assert_eq!(diff["added"][0]["id"], added);
+    assert_eq!(diff["added"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["removed"][0]["id"], removed);
+    assert_eq!(diff["removed"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["changed"].as_array().unwrap().len(), 1);
+    let fields = &diff["changed"][0]["fields"];
+    assert_eq!(diff["changed"][0]["id"], kept);
+    assert_eq!(
+        fields["text"],
+        json!({ "before": "Draft", "after": "Final" })
+    );
+    assert!(fields["updated_at"].is_object());
+    // Revision history is bookkeeping, not a change worth reporting.
+    assert!(fields.get("history").is_none(), "{}", fields);
+
+    call(&router, "create_snapshot", json!({ "name": "tuesday" })).unwrap();
+    let between = call_json(
+        &router,
+        "diff_snapshots",
+        json!({ "from": "monday", "to": "tuesday" }),
+    );
+    assert_eq!(between["added"], diff["added"]);
+    assert_eq!(between["removed"], diff["removed"]);
+    assert_eq!(between["changed"], diff["changed"]);
+    let unchanged = call_json(&router, "diff_snapshots", json!({ "from": "tuesday" }));
+    for key in ["added", "removed", "changed"] {
+        assert_eq!(unchanged[key], json!([]), "{}", key);
+    }
+    assert_eq!(
+        call(&router, "diff_snapshots", json!({ "from": "friday" })),
+        Err(ToolError::InvalidParameters(
+            "snapshot friday not found".to_string()
+        ))
+    );
+}
+
+#[test]
+fn plan_orders_todos_after_their_dependencies() {
+    let (_dir, router) = router();
+    let launch = add(&router, "Launch");
+    let test = add(&router, "Test");
+    let build = add(&router, "Build");
+    let design = call_json(
+        &router,
+        "add_todo",
+        json!({ "text": "Design", "done": true }),
+    )["id"]
+        .as_u64()
+        .unwrap();
+    let unrelated = add(&router, "Unrelated");
+    for (id, depends_on) in [
+        (launch, json!([test, build])),
+        (test, json!([build])),
+        (build, json!([design])),
+    ] {
+        call(
+            &router,
+            "set_dependencies",
+            json!({ "id": id, "depends_on": depends_on }),
+        )
+        .unwrap();
+    }
+    // Done todos are left out and count as satisfied; independent todos keep
+    // their list position relative to each other.
+    let planned: Vec<u64> = call_json(&router, "plan", json!({}))
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|todo| todo["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(planned, [build, test, launch, unrelated]);
+
+    call(
+        &router,
+        "set_dependencies",
+        json!({ "id": build, "depends_on": [launch] }),
+    )
+    .unwrap();
+    match call(&router, "plan", json!({})) {
+        Err(ToolError::ExecutionError(error)) => {
+            assert!(
+                error.starts_with("dependency cycle between todos "),
+                "{}",
+                error
+            );
+            for id in [launch, build] {
+                assert!(error.contains(&id.to_string()), "{}", error);
+            }
+        }
+        other => panic!("expected a cycle error, got {:?}", other),
+    }
+}
+
+#[test]
+fn archive_list_puts_a_list_away_until_restored() {
+    let (_dir, router) = router();
+    call(&router, "create_list", json!({ "name": "trip" })).unwrap();
+    for text in ["Book flights", "Pack"] {
+        call(&router, "add_todo", json!({ "text": text, "list": "trip" })).unwrap();
+    }
+    assert_eq!(
+        call_json(&router, "archive_list", json!({ "name": "trip" })),
+        json!({ "archived": "trip", "todos": 2 })
+    );
+    assert_eq!(router.get_lists().unwrap(), [DEFAULT_LIST]);
+    assert_eq!(
+        call_json(&router, "get_lists", json!({ "include_archived": true })),
+        json!({ "lists": [DEFAULT_LIST], "archived": ["trip"] })
+    );
+    assert_eq!(
+        call(
+            &router,
+            "add_todo",
+            json!({ "text": "Lost", "list": "trip" })
+        ),
+        Err(ToolError::InvalidParameters(
+            "list trip not found".to_string()
+        ))
+    );
+    for (name, error) in [
+        (DEFAULT_LIST, "the default list cannot be archived"),
+        ("trip", "list trip not found"),
+    ] {
+        assert_eq!(
+            call(&router, "archive_list", json!({ "name": name })),
+            Err(ToolError::InvalidParameters(error.to_string()))
+        );
+    }
+
+    assert_eq!(
+        call_json(&router, "restore_list", json!({ "name": "trip" })),
+        json!({ "restored": "trip", "todos": 2 })
+    );
+    assert_eq!(
+        texts(&router.get_todos("trip").unwrap()),
+        ["Book flights", "Pack"]
+    );
+    assert_eq!(
+        call_json(&router, "get_lists", json!({ "include_archived": true })),
+        json!({ "lists": [DEFAULT_LIST, "trip"], "archived": [] })
+    );
+    assert_eq!(
+        call(&router, "restore_list", json!({ "name": "trip" })),
+        Err(ToolError::InvalidParameters(
+            "archived list trip not found".to_string()
+        ))
+    );
+}
+
+#[test]
+fn upsert_todo_creates_or_updates_by_id() {
+    let (_dir, router) = router();
+    let created = call_json(
+        &router,
+        "upsert_todo",
+        json!({ "text": "Renew passport", "priority": "high", "tags": ["admin"] }),
+    );
+    assert_eq!(created["created"], true);
+    let id = created["todo"]["id"].as_u64().unwrap();
+
+    // Fields left out keep their value; those given replace it.
+    let updated = call_json(
+        &router,
+        "upsert_todo",
+        json!({ "id": id, "text": "Renew passport online", "tags": ["admin", "web"] }),
+    );
+    assert_eq!(updated["created"], false);
+    assert_eq!(updated["todo"]["text"], "Renew passport online");
+    assert_eq!(updated["todo"]["priority"], "high");
+    assert_eq!(updated["todo"]["tags"], json!(["admin", "web"]));
+    assert_eq!(updated["todo"]["history"][0]["text"], "Renew passport");
+
+    let done = call_json(
+        &router,
+        "upsert_todo",
+        json!({ "id": id, "text": "Renew passport online", "done": true }),
+    );
+    assert_eq!(done["todo"]["status"], "done");
+    assert!(done["todo"]["completed_at"].is_string());
+
+    // An id the list doesn't have yet creates the todo under that id.
+    let remote = call_json(
+        &router,
+        "upsert_todo",
+        json!({ "id": 77, "text": "From the server", "due_date": "2030-01-01T00:00:00Z" }),
+    );
+    assert_eq!(remote["created"], true);
+    assert_eq!(remote["todo"]["id"], 77);
+    assert_eq!(remote["todo"]["number"], 2);
+    assert_eq!(remote["todo"]["due_date"], "2030-01-01T00:00:00+00:00");
+    assert_eq!(ids(&router), [id, 77]);
+}
+
+#[test]
+fn export_table_shows_each_todos_status() {
+    let (_dir, router) = router();
+    for (text, status) in [
+        ("Plan", "todo"),
+        ("Build", "in_progress"),
+        ("Wait for review", "blocked"),
+        ("Ship", "done"),
+    ] {
+        let id = add(&router, text);
+        call(&router, "set_status", json!({ "id": id, "status": status })).unwrap();
+    }
+    let table = call(
+        &router,
+        "export_table",
+        json!({ "columns": ["text", "status"] }),
+    )
+    .unwrap();
+    assert_eq!(
+        table,
+        "| Text | Status |\n| --- | --- |\n\
+         | Plan | todo |\n\
+         | Build | in_progress |\n\
+         | Wait for review | blocked |\n\
+         | Ship | done |\n"
+    );
+}
@@ -1,67 +1,70 @@
-import { load, Store } from "@tauri-apps/plugin-store";
-import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
 import styles from "./App.module.css";
 
 type Todo = {
   id: number;
   text: string;
   done: boolean;
+  created_at?: string;
+  updated_at?: string;
+  completed_at?: string;
 };
 
-const store_path = "store.json";
-const todos_key = "todos";
+const list = "default";
 
-export default function App() {
-  const storeRef = useRef<Store | null>(null);
-  const [todos, setTodos] = useState<Todo[]>([]);
+type Content = { type: string; text: string };
 
-  useEffect(() => {
-    const init = async () => {
-      const store = await load(store_path, { autoSave: true });
-      storeRef.current = store;
+// Reads and changes go through the MCP tools, so they share the server's
+// write lock and see whichever storage backend TODO_STORAGE selects.
+const callTool = (name: string, args: Record<string, unknown>) =>
+  invoke<Content[]>("invoke_mcp_tool", {
+    name,
+    args: { ...args, list },
+  }).catch((e) => {
+    console.error(e);
+    return [] as Content[];
+  });
 
-      const todos = await store.get<Todo[]>(todos_key);
-      setTodos(todos ?? []);
-
-      const unlisten = await store.onKeyChange<Todo[]>(todos_key, (todos) => {
-        setTodos(todos ?? []);
-      });
+const fetchTodos = async () => {
+  const [content] = await callTool("get_todos", {});
+  return content ? (JSON.parse(content.text) as Todo[]) : undefined;
+};
 
-      const id = setInterval(async () => {
-        await store.reload();
-        const todos = await store.get<Todo[]>(todos_key);
-        setTodos(todos ?? []);
-      }, 1000);
+export default function App() {
+  const [todos, setTodos] = useState<Todo[]>([]);
 
-      return () => {
-        clearInterval(id);
-        unlisten();
-      };
-    };
+  const refresh = async () => {
+    const todos = await fetchTodos();
+    if (todos) {
+      setTodos(todos);
+    }
+  };
 
-    const p = init();
-    return () => {
-      p.then((cleanup) => cleanup());
-    };
+  useEffect(() => {
+    refresh();
+    // Picks up changes made by a separately running MCP server.
+    const id = setInterval(refresh, 1000);
+    return () => clearInterval(id);
   }, []);
 
   const addTodo = async (text: string) => {
-    const newTodo = { id: Date.now(), text, done: false };
-    await storeRef.current?.set(todos_key, [...todos, newTodo]);
+    await callTool("add_todo", { text });
+    await refresh();
   };
 
   const removeTodo = async (id: number) => {
-    await storeRef.current?.set(
-      todos_key,
-      todos.filter((todo) => todo.id !== id)
-    );
+    await callTool("remove_todo", { id });
+    await refresh();
   };
 
   const updateTodo = async (todo: Todo) => {
-    await storeRef.current?.set(
-      todos_key,
-      todos.map((t) => (t.id === todo.id ? todo : t))
-    );
+    await callTool("update_todo", {
+      id: todo.id,
+      text: todo.text,
+      done: todo.done,
+    });
+    await refresh();
   };
 
   return (